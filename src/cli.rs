@@ -2,9 +2,10 @@ use std::io::BufRead;
 use std::path::PathBuf;
 
 use clap::error::ErrorKind;
-use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 
 use crate::error_exit;
+pub use crate::sql::ParamStyle;
 use crate::utils::expand_paths;
 
 #[derive(Parser)]
@@ -12,6 +13,21 @@ use crate::utils::expand_paths;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[command(flatten)]
+    pub global: GlobalOptions,
+}
+
+/// Options taken by every command.
+#[derive(Args, Clone, Default)]
+pub struct GlobalOptions {
+    /// Unit of reported columns
+    #[arg(long, global = true, value_enum, default_value_t = ColumnUnit::Bytes)]
+    pub column_unit: ColumnUnit,
+
+    /// Keep the original of each rewritten file next to it, named with SUFFIX appended
+    #[arg(long, global = true, value_name = "SUFFIX")]
+    pub backup: Option<String>,
 }
 
 impl Cli {
@@ -23,10 +39,10 @@ impl Cli {
 
     pub fn tree_sitter(&self) -> (Option<&PathBuf>, bool) {
         let (path, no_ts) = match &self.command {
-            Commands::TS(Basics {
+            Commands::TS(TsOptions {
                 treesitter_query, ..
             }) => (treesitter_query, false),
-            Commands::Quotes(Basics {
+            Commands::Quotes(QuotesOptions {
                 treesitter_query, ..
             }) => (treesitter_query, false),
             Commands::Regex(RegexOptions {
@@ -34,6 +50,31 @@ impl Cli {
                 no_ts,
                 ..
             }) => (treesitter_query, *no_ts),
+            Commands::Inventory(InventoryOptions {
+                treesitter_query, ..
+            }) => (treesitter_query, false),
+            Commands::Check(CheckOptions {
+                treesitter_query, ..
+            }) => (treesitter_query, false),
+            Commands::Rename(RenameOptions {
+                treesitter_query, ..
+            }) => (treesitter_query, false),
+            Commands::Dupes(DupesOptions {
+                treesitter_query, ..
+            }) => (treesitter_query, false),
+            Commands::Explain(ExplainOptions {
+                treesitter_query, ..
+            }) => (treesitter_query, false),
+            Commands::Extract(ExtractOptions {
+                treesitter_query, ..
+            }) => (treesitter_query, false),
+            // these need the `execute` call itself so always use the default query
+            Commands::Lint(_) | Commands::Stats(_) | Commands::Parameterize(_) => {
+                return (None, false)
+            }
+            // inlining looks for loader calls rather than SQL strings
+            Commands::Inline(_) => return (None, false),
+            Commands::Undo => return (None, false),
         };
 
         (path.as_ref(), no_ts)
@@ -41,9 +82,20 @@ impl Cli {
 
     pub fn search_paths(&self) -> Vec<PathBuf> {
         let paths = match &self.command {
-            Commands::TS(Basics { search_paths, .. }) => search_paths,
-            Commands::Quotes(Basics { search_paths, .. }) => search_paths,
+            Commands::TS(TsOptions { search_paths, .. }) => search_paths,
+            Commands::Quotes(QuotesOptions { search_paths, .. }) => search_paths,
             Commands::Regex(RegexOptions { search_paths, .. }) => search_paths,
+            Commands::Inventory(InventoryOptions { search_paths, .. }) => search_paths,
+            Commands::Check(CheckOptions { search_paths, .. }) => search_paths,
+            Commands::Lint(LintOptions { search_paths, .. }) => search_paths,
+            Commands::Rename(RenameOptions { search_paths, .. }) => search_paths,
+            Commands::Dupes(DupesOptions { search_paths, .. }) => search_paths,
+            Commands::Stats(StatsOptions { search_paths, .. }) => search_paths,
+            Commands::Explain(ExplainOptions { search_paths, .. }) => search_paths,
+            Commands::Extract(ExtractOptions { search_paths, .. }) => search_paths,
+            Commands::Inline(InlineOptions { search_paths, .. }) => search_paths,
+            Commands::Parameterize(ParameterizeOptions { search_paths, .. }) => search_paths,
+            Commands::Undo => return Vec::new(),
         };
 
         if atty::is(atty::Stream::Stdin) && paths.is_empty() {
@@ -73,25 +125,108 @@ impl Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Find all matching tree-sitter nodes
-    TS(Basics),
+    TS(TsOptions),
 
-    /// Convert all SQL strings matched by tree-sitter in `"""` quotes
-    Quotes(Basics),
+    /// Convert all SQL strings matched by tree-sitter to `"""` or another quote style
+    Quotes(QuotesOptions),
 
     /// Pipe tree-sitter matched nodes to regex pattern matching
     Regex(RegexOptions),
+
+    /// List the tables and columns read and written by SQL strings
+    Inventory(InventoryOptions),
+
+    /// Verify that tables and columns used by SQL strings exist in a schema
+    Check(CheckOptions),
+
+    /// Check `execute` calls against configurable SQL rules
+    Lint(LintOptions),
+
+    /// Rename tables and columns in SQL strings without touching aliases, literals or comments
+    Rename(RenameOptions),
+
+    /// Find queries repeated across the code, ignoring formatting and literal values
+    Dupes(DupesOptions),
+
+    /// Summarize the SQL of `execute` calls: statement kinds, joins, nesting and dynamic SQL
+    Stats(StatsOptions),
+
+    /// Run `EXPLAIN QUERY PLAN` for SQL strings against a SQLite database
+    Explain(ExplainOptions),
+
+    /// Move SQL strings into `.sql` files, replacing them with a call loading the file
+    Extract(ExtractOptions),
+
+    /// Replace calls loading `.sql` files with the SQL as a `"""` string
+    Inline(InlineOptions),
+
+    /// Bind values spliced into `execute` SQL with f-strings, `.format()` or `%` as parameters
+    Parameterize(ParameterizeOptions),
+
+    /// Restore the files changed by the last run that rewrote any
+    Undo,
+}
+
+#[derive(Args)]
+pub struct QuotesOptions {
+    /// Path for treesitter query file
+    #[arg(short, long, value_name = "FILE")]
+    pub treesitter_query: Option<PathBuf>,
+
+    /// Quotes to convert to. Strings spanning several lines are only converted to triple quotes.
+    #[arg(short, long, value_enum, default_value_t = QuoteStyle::TripleDouble)]
+    pub style: QuoteStyle,
+
+    /// Only convert strings spanning several lines
+    #[arg(long, default_value_t = false)]
+    pub only_multiline: bool,
+
+    /// Put the SQL on its own lines, indented one level deeper than the line the string starts
+    /// on. Needs a triple-quoted style. Strings whose SQL has literals spanning lines are skipped.
+    #[arg(long, default_value_t = false)]
+    pub reflow: bool,
+
+    /// Merge SQL built from string literals joined by `+` or written next to each other into a
+    /// single string. Needs a triple-quoted style, and always looks for `execute` calls.
+    #[arg(long, default_value_t = false, conflicts_with = "treesitter_query")]
+    pub merge: bool,
+
+    /// Files to search through
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for QuotesOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Quotes(opts) => opts,
+            _ => unreachable!("can't get QuotesOptions from non-quotes commands"),
+        }
+    }
 }
 
 #[derive(Args)]
-pub struct Basics {
+pub struct TsOptions {
     /// Path for treesitter query file
     #[arg(short, long, value_name = "FILE")]
     pub treesitter_query: Option<PathBuf>,
 
+    /// List each statement of multi-statement SQL strings separately
+    #[arg(long, default_value_t = false)]
+    pub split_statements: bool,
+
     /// Files to search through
     pub search_paths: Vec<PathBuf>,
 }
 
+impl From<Commands> for TsOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::TS(opts) => opts,
+            _ => unreachable!("can't get TsOptions from non-ts commands"),
+        }
+    }
+}
+
 #[derive(Args)]
 pub struct RegexOptions {
     /// Path for treesitter query file.
@@ -129,6 +264,18 @@ pub struct RegexOptions {
     #[arg(short, long, default_value_t = false, requires = "replace")]
     pub confirm: bool,
 
+    /// Only match SQL code, skipping comments and string literals.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["comments_only", "literals_only"])]
+    pub code_only: bool,
+
+    /// Only match within SQL comments.
+    #[arg(long, default_value_t = false, conflicts_with = "literals_only")]
+    pub comments_only: bool,
+
+    /// Only match within SQL string literals.
+    #[arg(long, default_value_t = false)]
+    pub literals_only: bool,
+
     /// Don't use tree-sitter. AKA raw regex over the entire file(s).
     #[arg(long, default_value_t = false)]
     pub no_ts: bool,
@@ -136,6 +283,22 @@ pub struct RegexOptions {
     /// Number of lines to show before and after each match in `--confirm` mode. Default 15.
     #[arg(long, default_value_t = 15)]
     pub context: usize,
+
+    /// Also report `sql-parse: ignore` comments that don't suppress any match.
+    #[arg(long, default_value_t = false, conflicts_with = "replace")]
+    pub report_unused_ignores: bool,
+
+    /// Only report matches missing from this baseline file.
+    #[arg(long, value_name = "FILE", conflicts_with = "replace")]
+    pub baseline: Option<PathBuf>,
+
+    /// Record current matches in a baseline file instead of printing them.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["baseline", "replace"]
+    )]
+    pub write_baseline: Option<PathBuf>,
 }
 
 impl From<Commands> for RegexOptions {
@@ -147,6 +310,266 @@ impl From<Commands> for RegexOptions {
     }
 }
 
+#[derive(Args)]
+pub struct InventoryOptions {
+    /// Path for treesitter query file.
+    #[arg(short, long, value_name = "FILE")]
+    pub treesitter_query: Option<PathBuf>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for InventoryOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Inventory(opts) => opts,
+            _ => unreachable!("can't get InventoryOptions from non-inventory commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct CheckOptions {
+    /// Path for treesitter query file.
+    #[arg(short, long, value_name = "FILE")]
+    pub treesitter_query: Option<PathBuf>,
+
+    /// File of `CREATE TABLE` statements or a SQLite database to check against.
+    #[arg(short, long, value_name = "FILE")]
+    pub schema: PathBuf,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for CheckOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Check(opts) => opts,
+            _ => unreachable!("can't get CheckOptions from non-check commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct LintOptions {
+    /// Config file. Defaults to the closest `sql-parse.toml` up from the current directory.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Only run these rules.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    pub select: Vec<String>,
+
+    /// Skip these rules.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    pub ignore: Vec<String>,
+
+    /// Placeholder style expected by the database driver. Inferred per query when omitted.
+    #[arg(long, value_enum, value_name = "STYLE")]
+    pub paramstyle: Option<ParamStyle>,
+
+    /// Also report `sql-parse: ignore` comments that don't suppress anything.
+    #[arg(long, default_value_t = false)]
+    pub report_unused_ignores: bool,
+
+    /// Only report findings missing from this baseline file.
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+
+    /// Record current findings in a baseline file instead of reporting them.
+    #[arg(long, value_name = "FILE", conflicts_with = "baseline")]
+    pub write_baseline: Option<PathBuf>,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for LintOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Lint(opts) => opts,
+            _ => unreachable!("can't get LintOptions from non-lint commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct RenameOptions {
+    /// Path for treesitter query file.
+    #[arg(short, long, value_name = "FILE")]
+    pub treesitter_query: Option<PathBuf>,
+
+    /// Rename a table. Can be repeated.
+    #[arg(long, value_name = "OLD=NEW", required_unless_present = "column")]
+    pub table: Vec<String>,
+
+    /// Rename a column, optionally only for the given table. Can be repeated.
+    #[arg(long, value_name = "[TABLE.]OLD=NEW")]
+    pub column: Vec<String>,
+
+    /// Confirm each rename.
+    #[arg(short, long, default_value_t = false)]
+    pub confirm: bool,
+
+    /// Number of lines to show before and after each rename in `--confirm` mode. Default 15.
+    #[arg(long, default_value_t = 15)]
+    pub context: usize,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for RenameOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Rename(opts) => opts,
+            _ => unreachable!("can't get RenameOptions from non-rename commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct DupesOptions {
+    /// Path for treesitter query file.
+    #[arg(short, long, value_name = "FILE")]
+    pub treesitter_query: Option<PathBuf>,
+
+    /// How alike two queries have to be to be grouped, from 0 to 1. 1 only groups identical
+    /// queries.
+    #[arg(long, default_value_t = 0.9)]
+    pub similarity: f64,
+
+    /// Skip queries shorter than this many tokens, like `SELECT 1` or `COMMIT`.
+    #[arg(long, value_name = "COUNT", default_value_t = 5)]
+    pub min_tokens: usize,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for DupesOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Dupes(opts) => opts,
+            _ => unreachable!("can't get DupesOptions from non-dupes commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct StatsOptions {
+    /// Output format. `csv` only lists the per file numbers.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Number of longest queries to list.
+    #[arg(long, value_name = "COUNT", default_value_t = 10)]
+    pub top: usize,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for StatsOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Stats(opts) => opts,
+            _ => unreachable!("can't get StatsOptions from non-stats commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ExplainOptions {
+    /// Path for treesitter query file.
+    #[arg(short, long, value_name = "FILE")]
+    pub treesitter_query: Option<PathBuf>,
+
+    /// SQLite database file to explain queries against. Opened read-only.
+    #[arg(long, value_name = "FILE")]
+    pub sqlite: PathBuf,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for ExplainOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Explain(opts) => opts,
+            _ => unreachable!("can't get ExplainOptions from non-explain commands"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+/// What reported columns count, for editors and tools that disagree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColumnUnit {
+    /// UTF-8 bytes, like ripgrep and tree-sitter
+    #[default]
+    Bytes,
+    /// Unicode scalar values
+    Chars,
+    /// UTF-16 code units, like the Language Server Protocol
+    Utf16,
+}
+
+impl ColumnUnit {
+    /// The width of `text` in this unit.
+    pub fn len(&self, text: &str) -> usize {
+        match self {
+            ColumnUnit::Bytes => text.len(),
+            ColumnUnit::Chars => text.chars().count(),
+            ColumnUnit::Utf16 => text.encode_utf16().count(),
+        }
+    }
+
+    /// 1-based column following `line_prefix`, the start of a line.
+    pub fn column(&self, line_prefix: &str) -> usize {
+        self.len(line_prefix) + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QuoteStyle {
+    /// `"""SELECT 1"""`
+    TripleDouble,
+    /// `'''SELECT 1'''`
+    TripleSingle,
+    /// `"SELECT 1"`
+    Double,
+    /// `'SELECT 1'`
+    Single,
+}
+
+impl QuoteStyle {
+    pub fn delimiter(&self) -> &'static str {
+        match self {
+            QuoteStyle::TripleDouble => "\"\"\"",
+            QuoteStyle::TripleSingle => "'''",
+            QuoteStyle::Double => "\"",
+            QuoteStyle::Single => "'",
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 #[group(required = true, multiple = false)]
 pub struct RegexPattern {
@@ -158,3 +581,109 @@ pub struct RegexPattern {
     #[arg(long, value_name = "FILE", conflicts_with = "regex")]
     pub regex_file: Option<PathBuf>,
 }
+
+#[derive(Args)]
+pub struct ExtractOptions {
+    /// Path for treesitter query file.
+    #[arg(short, long, value_name = "FILE")]
+    pub treesitter_query: Option<PathBuf>,
+
+    /// Directory to write the `.sql` files to.
+    #[arg(short, long, value_name = "DIR", default_value = "sql")]
+    pub dir: PathBuf,
+
+    /// Name of each `.sql` file, without the extension. `{file}` is the Python file name,
+    /// `{function}` the enclosing function (`module` at the top level) and `{index}` counts the
+    /// strings within it from 1.
+    #[arg(
+        short,
+        long,
+        value_name = "TEMPLATE",
+        default_value = "{file}_{function}_{index}"
+    )]
+    pub name: String,
+
+    /// Python expression replacing each string. `{name}` is the `.sql` file name.
+    #[arg(
+        short,
+        long,
+        value_name = "TEMPLATE",
+        default_value = "load_sql(\"{name}\")"
+    )]
+    pub loader: String,
+
+    /// Only extract strings spanning at least this many lines.
+    #[arg(long, value_name = "COUNT", default_value_t = 1)]
+    pub min_lines: usize,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for ExtractOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Extract(opts) => opts,
+            _ => unreachable!("can't get ExtractOptions from non-extract commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct InlineOptions {
+    /// Directory the loaded files are relative to. Defaults to `sql`, or the working directory for
+    /// `open(...)` templates whose names are paths already.
+    #[arg(short, long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// Python expression loading a file. `{name}` is the file name, `.sql` is added when it has no
+    /// extension. Either quote style matches.
+    #[arg(
+        short,
+        long,
+        value_name = "TEMPLATE",
+        default_value = "load_sql(\"{name}\")"
+    )]
+    pub loader: String,
+
+    /// Only inline files of at most this many lines.
+    #[arg(long, value_name = "COUNT")]
+    pub max_lines: Option<usize>,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for InlineOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Inline(opts) => opts,
+            _ => unreachable!("can't get InlineOptions from non-inline commands"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ParameterizeOptions {
+    /// Config file to read `paramstyle` from. Defaults to the closest `sql-parse.toml` up from the
+    /// current directory.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Placeholder style expected by the database driver. Defaults to the configured one, then to
+    /// the style of placeholders already in the query, then to `format`.
+    #[arg(long, value_enum, value_name = "STYLE")]
+    pub paramstyle: Option<ParamStyle>,
+
+    /// Files to search through.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl From<Commands> for ParameterizeOptions {
+    fn from(value: Commands) -> Self {
+        match value {
+            Commands::Parameterize(opts) => opts,
+            _ => unreachable!("can't get ParameterizeOptions from non-parameterize commands"),
+        }
+    }
+}
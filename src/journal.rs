@@ -0,0 +1,179 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sql::hash;
+use crate::utils::{resolve_path, write_file};
+
+/// Where the originals of the files changed by the last rewriting run are kept for `undo`,
+/// relative to the working directory.
+pub const JOURNAL_DIR: &str = ".sql-parse/undo";
+const JOURNAL_FILE: &str = "journal.json";
+const VERSION: u32 = 1;
+
+/// The journal of this run, started by its first change.
+static JOURNAL: Mutex<Option<Journal>> = Mutex::new(None);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    /// Directory holding the journal and the originals.
+    #[serde(skip)]
+    dir: PathBuf,
+    version: u32,
+    pub entries: Vec<Entry>,
+}
+
+/// A file changed by a run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub path: PathBuf,
+    /// Name of the copy of the original in the journal directory, `None` for files the run
+    /// created.
+    pub original: Option<String>,
+    /// Hash of what the run wrote, so changes made since aren't undone.
+    pub written: String,
+}
+
+impl Journal {
+    pub fn load() -> anyhow::Result<Self> {
+        Self::load_from(Path::new(JOURNAL_DIR))
+    }
+
+    pub(crate) fn load_from(dir: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(dir.join(JOURNAL_FILE))?;
+        let mut journal: Self = serde_json::from_str(&text)?;
+        if journal.version != VERSION {
+            anyhow::bail!("unsupported journal version {}", journal.version);
+        }
+        journal.dir = dir.to_path_buf();
+        Ok(journal)
+    }
+
+    pub fn original(&self, entry: &Entry) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(name) = &entry.original else {
+            return Ok(None);
+        };
+        Ok(Some(std::fs::read(self.dir.join(name))?))
+    }
+
+    /// Forget the last run, once it's undone.
+    pub fn clear(&self) -> anyhow::Result<()> {
+        Ok(std::fs::remove_dir_all(&self.dir)?)
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        write_file(&self.dir.join(JOURNAL_FILE), json.as_bytes())
+    }
+}
+
+/// Whether `bytes` are still what a run wrote for `entry`.
+pub fn unchanged(entry: &Entry, bytes: &[u8]) -> bool {
+    hash(bytes) == entry.written
+}
+
+/// Record that `path`, holding `original` or created when `None`, is about to be replaced with
+/// `written`. The first change of a run replaces the journal of the last one, and a file changed
+/// twice keeps its first original.
+pub fn record(path: &Path, original: Option<&[u8]>, written: &[u8]) -> anyhow::Result<()> {
+    record_in(
+        Path::new(JOURNAL_DIR),
+        &mut JOURNAL.lock().unwrap(),
+        path,
+        original,
+        written,
+    )
+}
+
+/// [`record`] in the journal `dir`, with `journal` the state of this run.
+pub(crate) fn record_in(
+    dir: &Path,
+    journal: &mut Option<Journal>,
+    path: &Path,
+    original: Option<&[u8]>,
+    written: &[u8],
+) -> anyhow::Result<()> {
+    let journal = match journal.as_mut() {
+        Some(journal) => journal,
+        None => {
+            if dir.exists() {
+                std::fs::remove_dir_all(dir)?;
+            }
+            std::fs::create_dir_all(dir)?;
+            journal.insert(Journal {
+                dir: dir.to_path_buf(),
+                version: VERSION,
+                entries: Vec::new(),
+            })
+        }
+    };
+
+    let path = resolve_path(path);
+    let written = hash(written);
+    match journal.entries.iter_mut().find(|entry| entry.path == path) {
+        Some(entry) => entry.written = written,
+        None => {
+            let original = match original {
+                Some(bytes) => {
+                    let name = journal.entries.len().to_string();
+                    write_file(&dir.join(&name), bytes)?;
+                    Some(name)
+                }
+                None => None,
+            };
+            journal.entries.push(Entry {
+                path,
+                original,
+                written,
+            });
+        }
+    }
+    journal.save()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record_and_load() {
+        let root = std::env::temp_dir().join(format!("sql-parse-journal-{}", std::process::id()));
+        let dir = root.join("undo");
+        let (changed, created) = (root.join("users.py"), root.join("users.sql"));
+
+        // the journal of an earlier run is replaced
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("0"), "stale").unwrap();
+
+        let mut journal = None;
+        record_in(&dir, &mut journal, &changed, Some(b"old"), b"new").unwrap();
+        // the same file, however it's named
+        let renamed = dir.join("..").join("users.py");
+        record_in(&dir, &mut journal, &renamed, Some(b"new"), b"newer").unwrap();
+        record_in(&dir, &mut journal, &created, None, b"SELECT 1").unwrap();
+
+        let loaded = Journal::load_from(&dir).unwrap();
+        let paths = loaded
+            .entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, [resolve_path(&changed), resolve_path(&created)]);
+        let (first, second) = (&loaded.entries[0], &loaded.entries[1]);
+        assert_eq!(loaded.original(first).unwrap(), Some(b"old".to_vec()));
+        assert!(unchanged(first, b"newer") && !unchanged(first, b"new"));
+        assert_eq!(loaded.original(second).unwrap(), None);
+        assert!(unchanged(second, b"SELECT 1"));
+
+        loaded.clear().unwrap();
+        assert!(!dir.exists());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(JOURNAL_FILE), r#"{"version": 2, "entries": []}"#).unwrap();
+        let err = Journal::load_from(&dir).unwrap_err();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(err.to_string(), "unsupported journal version 2");
+    }
+}
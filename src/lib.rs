@@ -1,4 +1,7 @@
 pub mod cli;
+mod journal;
+mod lint;
 pub mod program;
+mod sql;
 mod treesitter;
 pub mod utils;
@@ -0,0 +1,179 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error_exit;
+use crate::utils::write_file;
+
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Key {
+    path: String,
+    fingerprint: String,
+    rule: String,
+}
+
+/// Findings recorded by `--write-baseline`, keyed by path, SQL fingerprint and rule so that
+/// they survive code moving around.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    counts: HashMap<Key, usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    findings: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    path: String,
+    fingerprint: String,
+    /// Rule id, or the pattern for `regex`.
+    rule: String,
+    count: usize,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file: BaselineFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if file.version != VERSION {
+            anyhow::bail!("unsupported baseline version {}", file.version);
+        }
+
+        let mut baseline = Self::default();
+        for entry in file.findings {
+            let key = Key {
+                path: entry.path,
+                fingerprint: entry.fingerprint,
+                rule: entry.rule,
+            };
+            *baseline.counts.entry(key).or_default() += entry.count;
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        // sorted so the file diffs nicely when regenerated
+        let sorted = self.counts.iter().collect::<BTreeMap<_, _>>();
+        let file = BaselineFile {
+            version: VERSION,
+            findings: sorted
+                .into_iter()
+                .map(|(key, &count)| Entry {
+                    path: key.path.clone(),
+                    fingerprint: key.fingerprint.clone(),
+                    rule: key.rule.clone(),
+                    count,
+                })
+                .collect(),
+        };
+        let mut json = serde_json::to_string_pretty(&file)?;
+        json.push('\n');
+        write_file(&path, json.as_bytes())
+    }
+
+    pub fn count(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn add(&mut self, path: &str, fingerprint: &str, rule: &str) {
+        *self.counts.entry(key(path, fingerprint, rule)).or_default() += 1;
+    }
+
+    /// Whether the finding is in the baseline, using up one of its recorded occurrences.
+    pub fn take(&mut self, path: &str, fingerprint: &str, rule: &str) -> bool {
+        match self.counts.get_mut(&key(path, fingerprint, rule)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn key(path: &str, fingerprint: &str, rule: &str) -> Key {
+    Key {
+        path: path.trim_start_matches("./").to_string(),
+        fingerprint: fingerprint.to_string(),
+        rule: rule.to_string(),
+    }
+}
+
+/// What to do with findings given `--baseline`/`--write-baseline`.
+pub enum BaselineMode {
+    /// Report everything.
+    Off,
+    /// Record every finding instead of reporting it.
+    Write(PathBuf, Baseline),
+    /// Only report findings missing from the baseline.
+    Filter(Baseline),
+}
+
+impl BaselineMode {
+    pub fn new(baseline: Option<PathBuf>, write_baseline: Option<PathBuf>) -> Self {
+        match (baseline, write_baseline) {
+            (_, Some(path)) => Self::Write(path, Baseline::default()),
+            (Some(path), None) => Self::Filter(Baseline::load(&path).unwrap_or_else(|err| {
+                error_exit!("Failed to load baseline {}: {}", path.display(), err)
+            })),
+            (None, None) => Self::Off,
+        }
+    }
+
+    /// Whether a finding should be reported.
+    pub fn report(&mut self, path: &str, fingerprint: &str, rule: &str) -> bool {
+        match self {
+            Self::Off => true,
+            Self::Write(_, baseline) => {
+                baseline.add(path, fingerprint, rule);
+                false
+            }
+            Self::Filter(baseline) => !baseline.take(path, fingerprint, rule),
+        }
+    }
+
+    /// Write out the baseline when recording one.
+    pub fn finish(&self) {
+        if let Self::Write(path, baseline) = self {
+            if let Err(err) = baseline.save(path) {
+                error_exit!("Failed to write baseline {}: {}", path.display(), err);
+            }
+            eprintln!(
+                "Recorded {} findings in {}",
+                baseline.count(),
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("sql-parse-baseline-{}.json", std::process::id()));
+        let mut baseline = Baseline::default();
+        baseline.add("./app/db.py", "00ff", "select-star");
+        baseline.add("app/db.py", "00ff", "select-star");
+        baseline.add("app/db.py", "00ff", "nolock");
+        baseline.save(&path).unwrap();
+
+        let mut loaded = Baseline::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.count(), 3);
+        assert!(loaded.take("app/db.py", "00ff", "select-star"));
+        assert!(loaded.take("app/db.py", "00ff", "select-star"));
+        assert!(!loaded.take("app/db.py", "00ff", "select-star"));
+        assert!(!loaded.take("app/db.py", "0000", "nolock"));
+        assert!(!loaded.take("app/other.py", "00ff", "nolock"));
+        assert!(loaded.take("app/db.py", "00ff", "nolock"));
+    }
+}
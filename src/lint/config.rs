@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::Severity;
+use crate::sql::ParamStyle;
+
+pub const CONFIG_FILE: &str = "sql-parse.toml";
+
+/// Project settings for `lint`, read from `sql-parse.toml`.
+///
+/// ```toml
+/// paramstyle = "qmark"
+/// ignore = ["order-by-ordinal"]
+///
+/// [rules.select-star]
+/// severity = "error"
+/// allow-in-exists = false
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub paramstyle: Option<ParamStyle>,
+    /// Only run these rules. Every rule runs when unset.
+    pub select: Option<Vec<String>>,
+    pub ignore: Vec<String>,
+    pub rules: HashMap<String, RuleConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RuleConfig {
+    /// Overrides the severity of every finding of the rule.
+    pub severity: Option<Severity>,
+    /// Rule specific options.
+    #[serde(flatten)]
+    pub options: toml::Table,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&text)?)
+    }
+
+    /// Look for a config file in the current directory and its parents.
+    pub fn find() -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        cwd.ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    pub fn is_enabled(&self, rule: &str) -> bool {
+        let selected = match &self.select {
+            Some(select) => select.iter().any(|id| id == rule),
+            None => true,
+        };
+        selected && !self.ignore.iter().any(|id| id == rule)
+    }
+
+    pub fn severity(&self, rule: &str) -> Option<Severity> {
+        self.rules.get(rule).and_then(|rule| rule.severity)
+    }
+
+    /// The options of `rule`, falling back to their defaults.
+    pub fn options<T: DeserializeOwned + Default>(&self, rule: &str) -> anyhow::Result<T> {
+        match self.rules.get(rule) {
+            Some(config) => toml::Value::Table(config.options.clone())
+                .try_into()
+                .with_context(|| format!("invalid options for rule `{rule}`")),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config: Config = toml::from_str(
+            r#"
+paramstyle = "named"
+select = ["select-star", "missing-where"]
+ignore = ["missing-where"]
+
+[rules.select-star]
+severity = "error"
+allow-in-exists = false
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.paramstyle, Some(ParamStyle::Named));
+        assert!(config.is_enabled("select-star"));
+        assert!(!config.is_enabled("missing-where"));
+        assert!(!config.is_enabled("nolock"));
+        assert_eq!(config.severity("select-star"), Some(Severity::Error));
+        assert_eq!(
+            config.rules["select-star"].options.get("allow-in-exists"),
+            Some(&toml::Value::Boolean(false))
+        );
+    }
+}
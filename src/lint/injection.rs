@@ -0,0 +1,43 @@
+use super::{Finding, Rule, Severity};
+use crate::treesitter::{ExecCall, InterpolationKind, Origin};
+
+pub const RULE: &str = "sql-injection";
+
+/// Flags every value spliced into the SQL text instead of being passed as a bound parameter.
+pub struct Injection;
+
+impl Rule for Injection {
+    fn id(&self) -> &'static str {
+        RULE
+    }
+
+    fn check(&self, call: &ExecCall, _code: &str) -> Vec<Finding> {
+        call.interpolations
+            .iter()
+            .map(|interpolation| {
+                let how = match interpolation.kind {
+                    InterpolationKind::FString => "f-string interpolation",
+                    InterpolationKind::Format => "`.format()` argument",
+                    InterpolationKind::Concat => "string concatenation",
+                    InterpolationKind::Percent => "`%` formatting",
+                };
+                let (severity, origin) = match interpolation.origin {
+                    Origin::Constant => (Severity::Info, "constant"),
+                    Origin::Unknown => (Severity::Warning, "local value"),
+                    Origin::Parameter => (Severity::Warning, "function parameter"),
+                    Origin::UserInput => (Severity::Error, "user input"),
+                };
+
+                Finding {
+                    rule: RULE,
+                    severity,
+                    range: interpolation.position.byte_range.clone(),
+                    message: format!(
+                        "SQL built with {how} of `{}` ({origin}); pass it as a bound parameter",
+                        interpolation.expr
+                    ),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,190 @@
+mod baseline;
+mod config;
+mod injection;
+mod params;
+mod statements;
+mod suppress;
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use anyhow::bail;
+use console::style;
+use serde::Deserialize;
+
+use crate::sql::lexer::Token;
+use crate::sql::statement_tokens;
+use crate::treesitter::ExecCall;
+pub use baseline::BaselineMode;
+pub use config::Config;
+pub use suppress::Suppressions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self {
+            Severity::Info => style("info").cyan(),
+            Severity::Warning => style("warning").yellow(),
+            Severity::Error => style("error").red(),
+        };
+        write!(f, "{severity}")
+    }
+}
+
+impl Severity {
+    pub fn color(&self) -> console::Color {
+        match self {
+            Severity::Info => console::Color::Cyan,
+            Severity::Warning => console::Color::Yellow,
+            Severity::Error => console::Color::Red,
+        }
+    }
+}
+
+/// A problem found in the SQL of a single `execute` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// Absolute byte range within the Python source.
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn display(&self) -> String {
+        format!("{}[{}] {}", self.severity, self.rule, self.message)
+    }
+}
+
+pub trait Rule {
+    /// Name used by the config file, `--select`/`--ignore` and in reports.
+    fn id(&self) -> &'static str;
+
+    fn check(&self, call: &ExecCall, code: &str) -> Vec<Finding>;
+}
+
+/// A rule looking at the SQL of each string one statement at a time.
+trait StatementRule {
+    const ID: &'static str;
+    const SEVERITY: Severity;
+
+    /// `tokens` holds a single statement without trivia. Ranges pushed to `found` are
+    /// relative to the SQL string.
+    fn check_statement(&self, tokens: &[Token<'_>], found: &mut Vec<(Range<usize>, String)>);
+}
+
+impl<T: StatementRule> Rule for T {
+    fn id(&self) -> &'static str {
+        T::ID
+    }
+
+    fn check(&self, call: &ExecCall, code: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for block in &call.blocks {
+            let offset = block.inner_text_range().start;
+            let mut found = Vec::new();
+            for statement in statement_tokens(block.inner_text(code)) {
+                self.check_statement(&statement, &mut found);
+            }
+            findings.extend(found.into_iter().map(|(range, message)| Finding {
+                rule: T::ID,
+                severity: T::SEVERITY,
+                range: range.start + offset..range.end + offset,
+                message,
+            }));
+        }
+        findings
+    }
+}
+
+/// Every rule id, in reporting order.
+pub const RULES: &[&str] = &[
+    injection::RULE,
+    params::COUNT_RULE,
+    params::STYLE_RULE,
+    statements::SelectStar::ID,
+    statements::MissingWhere::ID,
+    statements::ImplicitCrossJoin::ID,
+    statements::Nolock::ID,
+    statements::OrderByOrdinal::ID,
+];
+
+/// The enabled rules along with their configured severities.
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+    severities: HashMap<&'static str, Severity>,
+}
+
+impl Linter {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let configured = config
+            .select
+            .iter()
+            .flatten()
+            .chain(&config.ignore)
+            .chain(config.rules.keys());
+        for id in configured {
+            if !RULES.contains(&id.as_str()) {
+                bail!("unknown rule `{id}`");
+            }
+        }
+
+        let mut rules = Vec::new();
+        let mut severities = HashMap::new();
+        for &id in RULES.iter().filter(|id| config.is_enabled(id)) {
+            rules.push(new_rule(id, config)?);
+            if let Some(severity) = config.severity(id) {
+                severities.insert(id, severity);
+            }
+        }
+        Ok(Self { rules, severities })
+    }
+
+    pub fn rule_ids(&self) -> Vec<&'static str> {
+        self.rules.iter().map(|rule| rule.id()).collect()
+    }
+
+    pub fn check(&self, call: &ExecCall, code: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for rule in &self.rules {
+            let severity = self.severities.get(rule.id());
+            findings.extend(rule.check(call, code).into_iter().map(|mut finding| {
+                if let Some(&severity) = severity {
+                    finding.severity = severity;
+                }
+                finding
+            }));
+        }
+        findings.sort_by_key(|finding| finding.range.start);
+        findings
+    }
+}
+
+fn new_rule(id: &str, config: &Config) -> anyhow::Result<Box<dyn Rule>> {
+    use statements::*;
+
+    let rule: Box<dyn Rule> = match id {
+        injection::RULE => Box::new(injection::Injection),
+        params::COUNT_RULE => Box::new(params::ParamCount {
+            paramstyle: config.paramstyle,
+        }),
+        params::STYLE_RULE => Box::new(params::MixedStyles {
+            paramstyle: config.paramstyle,
+        }),
+        SelectStar::ID => Box::new(config.options::<SelectStar>(id)?),
+        MissingWhere::ID => Box::new(config.options::<MissingWhere>(id)?),
+        ImplicitCrossJoin::ID => Box::new(config.options::<ImplicitCrossJoin>(id)?),
+        Nolock::ID => Box::new(config.options::<Nolock>(id)?),
+        OrderByOrdinal::ID => Box::new(config.options::<OrderByOrdinal>(id)?),
+        _ => unreachable!("rule `{id}` isn't registered"),
+    };
+    Ok(rule)
+}
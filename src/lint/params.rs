@@ -0,0 +1,251 @@
+use std::ops::Range;
+
+use super::{Finding, Rule, Severity};
+use crate::sql::placeholders;
+use crate::sql::ParamStyle;
+use crate::treesitter::{ExecCall, InterpolationKind, ParamsShape};
+
+pub const COUNT_RULE: &str = "param-count";
+pub const STYLE_RULE: &str = "paramstyle";
+
+struct Marker<'a> {
+    style: ParamStyle,
+    key: Option<&'a str>,
+    text: &'a str,
+    /// Absolute byte range within the Python source.
+    range: Range<usize>,
+}
+
+/// Placeholders in the SQL, unless `%` formatting makes them belong to Python
+/// rather than the driver.
+fn markers<'a>(call: &'a ExecCall, code: &'a str) -> Option<Vec<Marker<'a>>> {
+    if call
+        .interpolations
+        .iter()
+        .any(|i| i.kind == InterpolationKind::Percent)
+    {
+        return None;
+    }
+
+    let markers = call
+        .blocks
+        .iter()
+        .flat_map(|block| {
+            let offset = block.inner_text_range().start;
+            let sql = block.inner_text(code);
+            placeholders(sql).into_iter().map(move |p| Marker {
+                style: p.style,
+                key: p.key,
+                text: &sql[p.range.clone()],
+                range: p.range.start + offset..p.range.end + offset,
+            })
+        })
+        .collect();
+    Some(markers)
+}
+
+/// The paramstyle a query is expected to use.
+fn query_style(markers: &[Marker], paramstyle: Option<ParamStyle>) -> Option<ParamStyle> {
+    paramstyle.or_else(|| markers.first().map(|m| m.style))
+}
+
+/// Flags placeholders of another paramstyle than the configured one, or than the first
+/// placeholder of the query.
+pub struct MixedStyles {
+    pub paramstyle: Option<ParamStyle>,
+}
+
+impl Rule for MixedStyles {
+    fn id(&self) -> &'static str {
+        STYLE_RULE
+    }
+
+    fn check(&self, call: &ExecCall, code: &str) -> Vec<Finding> {
+        let Some(markers) = markers(call, code) else {
+            return Vec::new();
+        };
+        let style = query_style(&markers, self.paramstyle);
+        markers
+            .iter()
+            .filter(|m| Some(m.style) != style)
+            .map(|marker| {
+                let message = match self.paramstyle {
+                    Some(expected) => format!(
+                        "`{}` is a {} placeholder but the paramstyle is {expected}",
+                        marker.text, marker.style
+                    ),
+                    None => format!(
+                        "`{}` mixes the {} paramstyle into a {} query",
+                        marker.text,
+                        marker.style,
+                        style.unwrap()
+                    ),
+                };
+                Finding {
+                    rule: STYLE_RULE,
+                    severity: Severity::Warning,
+                    range: marker.range.clone(),
+                    message,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Compares the placeholders in the SQL with the literal parameters passed alongside it.
+pub struct ParamCount {
+    pub paramstyle: Option<ParamStyle>,
+}
+
+impl Rule for ParamCount {
+    fn id(&self) -> &'static str {
+        COUNT_RULE
+    }
+
+    fn check(&self, call: &ExecCall, code: &str) -> Vec<Finding> {
+        // placeholders can't be counted when part of the SQL is only known at runtime
+        if !call.interpolations.is_empty() {
+            return Vec::new();
+        }
+        let Some(markers) = markers(call, code) else {
+            return Vec::new();
+        };
+        // and the count means little once styles are mixed
+        let style = query_style(&markers, self.paramstyle);
+        if markers.iter().any(|m| Some(m.style) != style) {
+            return Vec::new();
+        }
+        count_findings(call, &markers, style)
+    }
+}
+
+fn count_findings(call: &ExecCall, markers: &[Marker], style: Option<ParamStyle>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let params_range = call
+        .params
+        .as_ref()
+        .map(|params| params.position.byte_range.clone())
+        .unwrap_or_else(|| call.sql_arg.byte_range.clone());
+    let shape = call.params.as_ref().map(|params| &params.shape);
+    let mut count_finding = |severity, range, message| {
+        findings.push(Finding {
+            rule: COUNT_RULE,
+            severity,
+            range,
+            message,
+        })
+    };
+
+    match style {
+        None => match shape {
+            Some(ParamsShape::Sequence(n)) if *n > 0 => count_finding(
+                Severity::Error,
+                params_range,
+                format!(
+                    "{} passed but the SQL has no placeholders",
+                    plural(*n, "parameter")
+                ),
+            ),
+            Some(ParamsShape::Mapping(keys)) if !keys.is_empty() => count_finding(
+                Severity::Warning,
+                params_range,
+                format!(
+                    "{} passed but the SQL has no placeholders",
+                    plural(keys.len(), "parameter")
+                ),
+            ),
+            _ => (),
+        },
+        Some(style @ (ParamStyle::Qmark | ParamStyle::Format | ParamStyle::Numeric)) => {
+            let expected = match style {
+                ParamStyle::Numeric => markers
+                    .iter()
+                    .filter_map(|m| m.key?.parse::<usize>().ok())
+                    .max()
+                    .unwrap_or_default(),
+                _ => markers.len(),
+            };
+            match shape {
+                None => count_finding(
+                    Severity::Error,
+                    params_range,
+                    format!(
+                        "SQL has {} but no parameters are passed",
+                        plural(expected, "placeholder")
+                    ),
+                ),
+                Some(ParamsShape::Sequence(n)) if *n != expected => count_finding(
+                    Severity::Error,
+                    params_range,
+                    format!(
+                        "SQL has {} but {} passed",
+                        plural(expected, "placeholder"),
+                        plural(*n, "parameter")
+                    ),
+                ),
+                Some(ParamsShape::Mapping(_)) => count_finding(
+                    Severity::Error,
+                    params_range,
+                    format!("{style} placeholders take a sequence of parameters, not a dict"),
+                ),
+                _ => (),
+            }
+        }
+        Some(style) => {
+            let mut names = Vec::new();
+            for marker in markers {
+                if let Some(key) = marker
+                    .key
+                    .filter(|key| !names.iter().any(|(k, _)| k == key))
+                {
+                    names.push((key, marker.range.clone()));
+                }
+            }
+            match shape {
+                None => count_finding(
+                    Severity::Error,
+                    params_range,
+                    format!(
+                        "SQL has {} but no parameters are passed",
+                        plural(names.len(), "placeholder")
+                    ),
+                ),
+                Some(ParamsShape::Sequence(_)) => count_finding(
+                    Severity::Error,
+                    params_range,
+                    format!("{style} placeholders take a dict of parameters, not a sequence"),
+                ),
+                Some(ParamsShape::Mapping(keys)) => {
+                    for (name, range) in &names {
+                        if !keys.iter().any(|key| key == name) {
+                            count_finding(
+                                Severity::Error,
+                                range.clone(),
+                                format!("no parameter passed for `{name}`"),
+                            );
+                        }
+                    }
+                    for key in keys {
+                        if !names.iter().any(|(name, _)| name == key) {
+                            count_finding(
+                                Severity::Warning,
+                                params_range.clone(),
+                                format!("parameter `{key}` isn't used by the SQL"),
+                            );
+                        }
+                    }
+                }
+                Some(ParamsShape::Dynamic) => (),
+            }
+        }
+    }
+    findings
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{count} {word}")
+    } else {
+        format!("{count} {word}s")
+    }
+}
@@ -0,0 +1,389 @@
+use std::ops::Range;
+
+use serde::Deserialize;
+
+use super::{Severity, StatementRule};
+use crate::sql::lexer::{Token, TokenKind};
+
+/// Keywords ending the table list of a `FROM` clause or the items of an `ORDER BY`.
+const CLAUSE_END: &[&str] = &[
+    "CONFLICT",
+    "DUPLICATE",
+    "EXCEPT",
+    "FETCH",
+    "FOR",
+    "GROUP",
+    "HAVING",
+    "INTERSECT",
+    "LIMIT",
+    "OFFSET",
+    "ORDER",
+    "RETURNING",
+    "UNION",
+    "WHERE",
+    "WINDOW",
+];
+
+fn ends_clause(tok: &Token<'_>) -> bool {
+    CLAUSE_END.iter().any(|kw| tok.is_keyword(kw))
+}
+
+/// Index of the `(` enclosing `idx`, if any.
+fn enclosing_paren(tokens: &[Token<'_>], idx: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, tok) in tokens[..idx].iter().enumerate().rev() {
+        if tok.is_punct(")") {
+            depth += 1;
+        } else if tok.is_punct("(") {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// Whether `idx` sits in a `SELECT` list, ie. `SELECT` is the closest clause keyword
+/// before it at the same nesting level.
+fn in_select_list(tokens: &[Token<'_>], idx: usize) -> bool {
+    let mut depth = 0;
+    for tok in tokens[..idx].iter().rev() {
+        if tok.is_punct(")") {
+            depth += 1;
+        } else if tok.is_punct("(") {
+            if depth == 0 {
+                return false;
+            }
+            depth -= 1;
+        } else if depth == 0 && tok.kind == TokenKind::Word {
+            if tok.is_keyword("SELECT") {
+                return true;
+            }
+            if ["FROM", "SET", "VALUES", "BY", "ON"]
+                .iter()
+                .any(|kw| tok.is_keyword(kw))
+                || ends_clause(tok)
+            {
+                return false;
+            }
+        }
+    }
+    false
+}
+
+/// `SELECT *` and `SELECT t.*`.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SelectStar {
+    /// Allow `EXISTS (SELECT * ...)`, where the columns don't matter.
+    allow_in_exists: bool,
+}
+
+impl Default for SelectStar {
+    fn default() -> Self {
+        Self {
+            allow_in_exists: true,
+        }
+    }
+}
+
+impl StatementRule for SelectStar {
+    const ID: &'static str = "select-star";
+    const SEVERITY: Severity = Severity::Warning;
+
+    fn check_statement(&self, tokens: &[Token<'_>], found: &mut Vec<(Range<usize>, String)>) {
+        for (idx, tok) in tokens.iter().enumerate() {
+            if !tok.is_punct("*") || idx == 0 {
+                continue;
+            }
+            let prev = &tokens[idx - 1];
+            let is_star = ["SELECT", "DISTINCT", "ALL"]
+                .iter()
+                .any(|kw| prev.is_keyword(kw))
+                || ((prev.is_punct(",") || prev.is_punct(".")) && in_select_list(tokens, idx));
+            if !is_star {
+                continue;
+            }
+
+            let in_exists = enclosing_paren(tokens, idx)
+                .and_then(|paren| paren.checked_sub(1))
+                .is_some_and(|before| tokens[before].is_keyword("EXISTS"));
+            if in_exists && self.allow_in_exists {
+                continue;
+            }
+
+            let range = if prev.is_punct(".") && idx >= 2 {
+                tokens[idx - 2].range.start..tok.range.end
+            } else {
+                tok.range.clone()
+            };
+            found.push((
+                range,
+                "`SELECT *` breaks when columns change; list them".into(),
+            ));
+        }
+    }
+}
+
+/// `DELETE` and `UPDATE` statements changing every row.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct MissingWhere {
+    /// Statements that need a `WHERE` clause.
+    statements: Vec<String>,
+}
+
+impl Default for MissingWhere {
+    fn default() -> Self {
+        Self {
+            statements: vec!["DELETE".into(), "UPDATE".into()],
+        }
+    }
+}
+
+impl StatementRule for MissingWhere {
+    const ID: &'static str = "missing-where";
+    const SEVERITY: Severity = Severity::Error;
+
+    fn check_statement(&self, tokens: &[Token<'_>], found: &mut Vec<(Range<usize>, String)>) {
+        let mut depth = 0;
+        let mut verb = None;
+        for (idx, tok) in tokens.iter().enumerate() {
+            if tok.is_punct("(") {
+                depth += 1;
+            } else if tok.is_punct(")") {
+                depth -= 1;
+            } else if depth == 0 {
+                if verb.is_some() {
+                    if tok.is_keyword("WHERE") {
+                        return;
+                    }
+                    continue;
+                }
+
+                // the verb starts the statement or follows a CTE list
+                let starts_statement =
+                    idx == 0 || (tokens[0].is_keyword("WITH") && tokens[idx - 1].is_punct(")"));
+                if starts_statement && self.statements.iter().any(|kw| tok.is_keyword(kw)) {
+                    verb = Some(tok);
+                } else if idx == 0 && !tok.is_keyword("WITH") {
+                    return;
+                }
+            }
+        }
+
+        if let Some(verb) = verb {
+            found.push((
+                verb.range.clone(),
+                format!(
+                    "`{}` without `WHERE` affects every row",
+                    verb.text.to_uppercase()
+                ),
+            ));
+        }
+    }
+}
+
+/// `FROM a, b` instead of an explicit `JOIN`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImplicitCrossJoin {}
+
+impl StatementRule for ImplicitCrossJoin {
+    const ID: &'static str = "implicit-cross-join";
+    const SEVERITY: Severity = Severity::Warning;
+
+    fn check_statement(&self, tokens: &[Token<'_>], found: &mut Vec<(Range<usize>, String)>) {
+        for (from, _) in tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, tok)| tok.is_keyword("FROM"))
+        {
+            let mut depth = 0;
+            for (idx, tok) in tokens.iter().enumerate().skip(from + 1) {
+                if tok.is_punct("(") {
+                    depth += 1;
+                } else if tok.is_punct(")") {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                } else if depth == 0 && ends_clause(tok) {
+                    break;
+                } else if depth == 0 && tok.is_punct(",") {
+                    let range = tokens
+                        .get(idx + 1)
+                        .map(|next| next.range.clone())
+                        .unwrap_or_else(|| tok.range.clone());
+                    found.push((
+                        range,
+                        "implicit cross join; use an explicit `JOIN ... ON`".into(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Table hints allowing dirty reads.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Nolock {
+    hints: Vec<String>,
+}
+
+impl Default for Nolock {
+    fn default() -> Self {
+        Self {
+            hints: vec!["NOLOCK".into(), "READUNCOMMITTED".into()],
+        }
+    }
+}
+
+impl StatementRule for Nolock {
+    const ID: &'static str = "nolock";
+    const SEVERITY: Severity = Severity::Warning;
+
+    fn check_statement(&self, tokens: &[Token<'_>], found: &mut Vec<(Range<usize>, String)>) {
+        for tok in tokens {
+            if self.hints.iter().any(|hint| tok.is_keyword(hint)) {
+                found.push((
+                    tok.range.clone(),
+                    format!(
+                        "`{}` reads uncommitted data and can return rows twice or not at all",
+                        tok.text.to_uppercase()
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// `ORDER BY 2` referring to a select list item by position.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct OrderByOrdinal {
+    /// Check `GROUP BY` as well.
+    group_by: bool,
+}
+
+impl StatementRule for OrderByOrdinal {
+    const ID: &'static str = "order-by-ordinal";
+    const SEVERITY: Severity = Severity::Warning;
+
+    fn check_statement(&self, tokens: &[Token<'_>], found: &mut Vec<(Range<usize>, String)>) {
+        for (by, _) in tokens.iter().enumerate().filter(|(idx, tok)| {
+            tok.is_keyword("BY")
+                && idx.checked_sub(1).is_some_and(|prev| {
+                    tokens[prev].is_keyword("ORDER")
+                        || (self.group_by && tokens[prev].is_keyword("GROUP"))
+                })
+        }) {
+            let clause = tokens[by - 1].text.to_uppercase();
+            let mut depth = 0;
+            let mut item_start = true;
+            for (idx, tok) in tokens.iter().enumerate().skip(by + 1) {
+                if tok.is_punct("(") {
+                    depth += 1;
+                } else if tok.is_punct(")") {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                } else if depth == 0 && (ends_clause(tok) || tok.is_punct(";")) {
+                    break;
+                } else if depth == 0 && tok.is_punct(",") {
+                    item_start = true;
+                    continue;
+                }
+
+                // a bare number, not the start of an expression like `1 + x`
+                let is_ordinal = item_start
+                    && tok.kind == TokenKind::Number
+                    && match tokens.get(idx + 1) {
+                        Some(next) => {
+                            next.is_punct(",") || next.is_punct(")") || next.kind == TokenKind::Word
+                        }
+                        None => true,
+                    };
+                if is_ordinal {
+                    found.push((
+                        tok.range.clone(),
+                        format!(
+                            "`{clause} BY {}` refers to a column by position; name the column",
+                            tok.text
+                        ),
+                    ));
+                }
+                item_start = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sql::statement_tokens;
+
+    fn check(rule: &impl StatementRule, sql: &str) -> Vec<String> {
+        let mut found = Vec::new();
+        for statement in statement_tokens(sql) {
+            rule.check_statement(&statement, &mut found);
+        }
+        found
+            .into_iter()
+            .map(|(range, _)| sql[range].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn select_star() {
+        let sql = "SELECT * FROM t; SELECT DISTINCT a, u.* FROM u WHERE EXISTS (SELECT * FROM v); SELECT COUNT(*), 2 * 3 FROM w";
+        assert_eq!(check(&SelectStar::default(), sql), vec!["*", "u.*"]);
+        assert_eq!(
+            check(
+                &SelectStar {
+                    allow_in_exists: false
+                },
+                sql
+            ),
+            vec!["*", "u.*", "*"]
+        );
+    }
+
+    #[test]
+    fn missing_where() {
+        let sql = "DELETE FROM t; UPDATE t SET a = 1 WHERE id = 2; WITH x AS (SELECT 1) UPDATE t SET a = (SELECT b FROM u WHERE c = 1); INSERT INTO t VALUES (1) ON CONFLICT DO UPDATE SET a = 1";
+        assert_eq!(
+            check(&MissingWhere::default(), sql),
+            vec!["DELETE", "UPDATE"]
+        );
+    }
+
+    #[test]
+    fn implicit_cross_join() {
+        let sql = "SELECT a, b FROM t, u JOIN v ON v.id = t.id WHERE x IN (1, 2); SELECT * FROM t JOIN u ON t.id = u.id";
+        assert_eq!(check(&ImplicitCrossJoin::default(), sql), vec!["u"]);
+    }
+
+    #[test]
+    fn nolock() {
+        let sql = "SELECT * FROM t WITH (nolock) JOIN u (READUNCOMMITTED) ON 1 = 1";
+        assert_eq!(
+            check(&Nolock::default(), sql),
+            vec!["nolock", "READUNCOMMITTED"]
+        );
+    }
+
+    #[test]
+    fn order_by_ordinal() {
+        let sql = "SELECT a, b FROM t GROUP BY 1, 2 ORDER BY 2 DESC, a, 1 + b LIMIT 3";
+        assert_eq!(check(&OrderByOrdinal::default(), sql), vec!["2"]);
+        assert_eq!(
+            check(&OrderByOrdinal { group_by: true }, sql),
+            vec!["1", "2", "2"]
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+use regex::Regex;
+use tree_sitter::Node;
+
+use super::{Finding, Severity};
+use crate::sql::lexer::{tokenize, TokenKind};
+use crate::treesitter::{new_parser, parser_tree, SqlBlock};
+use crate::utils::line_number;
+
+pub const UNUSED_RULE: &str = "unused-ignore";
+
+const DIRECTIVE_PATTERN: &str = r"sql-parse:\s*ignore(?:\[([^\]]*)\])?";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// A Python comment after code, covering anything spanning its line.
+    Trailing(usize),
+    /// A Python comment on a line of its own, covering anything starting on the next line.
+    Standalone(usize),
+    /// A comment inside the SQL, covering the string holding it.
+    Sql,
+}
+
+#[derive(Debug)]
+struct Directive {
+    /// Byte range of the comment.
+    range: Range<usize>,
+    scope: Scope,
+    /// Rules to suppress along with whether each was used. Every rule when `None`.
+    rules: Option<Vec<(String, bool)>>,
+    used: bool,
+}
+
+/// `# sql-parse: ignore[rule-id]` comments in Python and `-- sql-parse: ignore` comments in SQL.
+#[derive(Debug)]
+pub struct Suppressions {
+    directives: Vec<Directive>,
+}
+
+impl Suppressions {
+    pub fn new(code: &str, blocks: &[SqlBlock]) -> Self {
+        let re = Regex::new(DIRECTIVE_PATTERN).unwrap();
+        let mut directives = Vec::new();
+        let mut push = |range: Range<usize>, scope| {
+            if let Some(caps) = re.captures(&code[range.clone()]) {
+                let rules = caps.get(1).map(|rules| {
+                    rules
+                        .as_str()
+                        .split(',')
+                        .map(|rule| (rule.trim().to_string(), false))
+                        .filter(|(rule, _)| !rule.is_empty())
+                        .collect()
+                });
+                directives.push(Directive {
+                    range,
+                    scope,
+                    rules,
+                    used: false,
+                });
+            }
+        };
+
+        let mut parser = new_parser();
+        let tree = parser_tree(&mut parser, code);
+        let mut comments = Vec::new();
+        collect_comments(tree.root_node(), &mut comments);
+        for comment in comments {
+            let range = comment.byte_range();
+            let line_start = code[..range.start].rfind('\n').map_or(0, |idx| idx + 1);
+            let row = line_number(code, range.start);
+            let scope = if code[line_start..range.start].trim().is_empty() {
+                Scope::Standalone(row)
+            } else {
+                Scope::Trailing(row)
+            };
+            push(range, scope);
+        }
+
+        for block in blocks {
+            let offset = block.inner_text_range().start;
+            for token in tokenize(block.inner_text(code)) {
+                if token.kind == TokenKind::Comment {
+                    push(
+                        token.range.start + offset..token.range.end + offset,
+                        Scope::Sql,
+                    );
+                }
+            }
+        }
+
+        Self { directives }
+    }
+
+    /// Whether findings of `rule` within the byte range `target` are suppressed. Matching
+    /// directives are marked as used.
+    pub fn suppresses(&mut self, code: &str, target: Range<usize>, rule: &str) -> bool {
+        let first_row = line_number(code, target.start);
+        let last_row = line_number(code, target.end);
+
+        let mut suppressed = false;
+        for directive in &mut self.directives {
+            let covers = match directive.scope {
+                Scope::Trailing(row) => (first_row..=last_row).contains(&row),
+                Scope::Standalone(row) => row + 1 == first_row,
+                Scope::Sql => target.contains(&directive.range.start),
+            };
+            if !covers {
+                continue;
+            }
+
+            match &mut directive.rules {
+                None => directive.used = true,
+                Some(rules) => match rules.iter_mut().find(|(id, _)| id == rule) {
+                    Some((_, used)) => {
+                        *used = true;
+                        directive.used = true;
+                    }
+                    None => continue,
+                },
+            }
+            suppressed = true;
+        }
+        suppressed
+    }
+
+    /// Directives, or rules listed in them, that didn't suppress anything. Rules outside of
+    /// `active` are left alone as they may be used by other commands.
+    pub fn unused(&self, active: &[&str]) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for directive in &self.directives {
+            let message = match &directive.rules {
+                None if !directive.used => "unused `sql-parse: ignore`".to_string(),
+                None => continue,
+                Some(rules) => {
+                    let unused = rules
+                        .iter()
+                        .filter(|(id, used)| !used && active.contains(&id.as_str()))
+                        .map(|(id, _)| format!("`{id}`"))
+                        .collect::<Vec<_>>();
+                    if unused.is_empty() {
+                        continue;
+                    }
+                    format!("unused `sql-parse: ignore` for {}", unused.join(", "))
+                }
+            };
+            findings.push(Finding {
+                rule: UNUSED_RULE,
+                severity: Severity::Warning,
+                range: directive.range.clone(),
+                message,
+            });
+        }
+        findings
+    }
+}
+
+fn collect_comments<'t>(node: Node<'t>, found: &mut Vec<Node<'t>>) {
+    if node.kind() == "comment" {
+        found.push(node);
+        return;
+    }
+    let mut tc = node.walk();
+    for child in node.children(&mut tc) {
+        collect_comments(child, found);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treesitter::{Exec, TreesitterQuery};
+
+    #[test]
+    fn scopes() {
+        let code = r#"
+crs.execute("SELECT 1")  # sql-parse: ignore[a]
+# sql-parse: ignore[b, c]
+crs.execute("""
+    SELECT 2
+""")
+crs.execute("SELECT 3 -- sql-parse: ignore")
+crs.execute("SELECT 4")  # unrelated
+"#;
+        let blocks = Exec::new().sql_blocks(code);
+        let mut suppressions = Suppressions::new(code, &blocks);
+        let target = |idx: usize| {
+            let block: &SqlBlock = &blocks[idx];
+            block.string_start.byte_range.start..block.string_end.byte_range.end
+        };
+
+        assert!(suppressions.suppresses(code, target(0), "a"));
+        assert!(!suppressions.suppresses(code, target(0), "b"));
+        assert!(suppressions.suppresses(code, target(1), "b"));
+        assert!(!suppressions.suppresses(code, target(1), "a"));
+        assert!(suppressions.suppresses(code, target(2), "anything"));
+        assert!(!suppressions.suppresses(code, target(3), "a"));
+
+        let unused = suppressions
+            .unused(&["a", "b", "c"])
+            .into_iter()
+            .map(|finding| finding.message)
+            .collect::<Vec<_>>();
+        assert_eq!(unused, vec!["unused `sql-parse: ignore` for `c`"]);
+    }
+}
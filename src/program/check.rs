@@ -0,0 +1,205 @@
+use std::path::PathBuf;
+
+use console::style;
+
+use super::Program;
+use crate::cli::{CheckOptions, Cli, ColumnUnit};
+use crate::error_exit;
+use crate::sql::{references, Access, RefKind, Reference, Schema};
+use crate::treesitter::{ts_query_factory, TreesitterQuery};
+use crate::utils::*;
+
+pub(crate) struct Check {
+    treesitter: Box<dyn TreesitterQuery>,
+    search_paths: Vec<PathBuf>,
+    column_unit: ColumnUnit,
+    schema: Schema,
+}
+
+#[derive(Debug, PartialEq)]
+struct Unknown {
+    offset: usize,
+    message: String,
+}
+
+impl Program for Check {
+    fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: CheckOptions = cli.command.into();
+
+        let schema = Schema::load(&opts.schema).unwrap_or_else(|err| {
+            error_exit!("Failed to load schema {}: {}", opts.schema.display(), err)
+        });
+        if schema.is_empty() {
+            eprintln!("No tables found in schema: {}", opts.schema.display());
+        }
+
+        Self {
+            treesitter,
+            search_paths,
+            column_unit: cli.global.column_unit,
+            schema,
+        }
+    }
+
+    fn run(&mut self) {
+        let mut unknown_count = 0;
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (code, path) in iter_valid_files(&search_paths) {
+            let path = path.as_path().to_str().unwrap();
+            for unknown in self.check_file(&code) {
+                let (line, col) = line_col(&code, unknown.offset, self.column_unit);
+                print(path, line, Some(col), &unknown.message);
+                unknown_count += 1;
+            }
+        }
+
+        if unknown_count > 0 {
+            std::process::exit(1);
+        }
+    }
+}
+
+impl Check {
+    fn check_file(&mut self, code: &str) -> Vec<Unknown> {
+        let mut unknowns = Vec::new();
+        for block in self.treesitter.sql_blocks(code) {
+            let offset = block.inner_text_range().start;
+            let refs = references(block.inner_text(code));
+            unknowns.extend(self.check_refs(&refs).into_iter().map(|mut unknown| {
+                unknown.offset += offset;
+                unknown
+            }));
+        }
+        unknowns
+    }
+
+    fn check_refs(&self, refs: &[Reference]) -> Vec<Unknown> {
+        let mut unknowns = Vec::new();
+        // queries may create the tables they use, so DDL references are left alone
+        let refs = refs.iter().filter(|r| r.access != Access::Ddl);
+
+        for reference in refs.clone() {
+            let message = match reference.kind {
+                RefKind::Table => self.check_table(reference),
+                RefKind::Column => self.check_column(reference, refs.clone()),
+            };
+            if let Some(message) = message {
+                unknowns.push(Unknown {
+                    offset: reference.range.start,
+                    message,
+                });
+            }
+        }
+        unknowns
+    }
+
+    fn check_table(&self, reference: &Reference) -> Option<String> {
+        if reference.name.starts_with('#') || self.schema.table(&reference.name).is_some() {
+            return None;
+        }
+
+        let mut message = format!("unknown table {}", style(&reference.name).red());
+        if let Some(suggestion) = self.schema.suggest_table(&reference.name) {
+            message.push_str(&format!(", did you mean {}?", style(suggestion).green()));
+        }
+        Some(message)
+    }
+
+    fn check_column<'a>(
+        &self,
+        reference: &Reference,
+        mut refs: impl Iterator<Item = &'a Reference>,
+    ) -> Option<String> {
+        let column = &reference.name;
+        let table = match &reference.table {
+            Some(table) => self.schema.table(table)?,
+            // unresolved columns only need to exist on one of the statement's known tables
+            None => {
+                let found = refs.any(|r| {
+                    r.kind == RefKind::Table
+                        && self
+                            .schema
+                            .table(&r.name)
+                            .map(|table| table.has_column(column))
+                            .unwrap_or(true)
+                });
+                return (!found).then(|| format!("unknown column {}", style(column).red()));
+            }
+        };
+
+        if table.has_column(column) {
+            return None;
+        }
+
+        let mut message = format!(
+            "unknown column {} on table {}",
+            style(column).red(),
+            table.name
+        );
+        if let Some(suggestion) = table.suggest_column(column) {
+            message.push_str(&format!(", did you mean {}?", style(suggestion).green()));
+        }
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treesitter::Exec;
+
+    fn check(code: &str) -> Vec<(usize, usize, String)> {
+        let mut check = Check {
+            treesitter: Box::new(Exec::new()),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            schema: Schema::from_ddl(
+                "CREATE TABLE users (id INT, name TEXT); CREATE TABLE orders (id INT, user_id INT);",
+            ),
+        };
+        check
+            .check_file(code)
+            .into_iter()
+            .map(|unknown| {
+                let (line, col) = line_col(code, unknown.offset, ColumnUnit::Bytes);
+                (
+                    line,
+                    col,
+                    console::strip_ansi_codes(&unknown.message).into(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn known_identifiers() {
+        let code = r#"
+crs.execute("SELECT u.name, o.id FROM users u JOIN orders o ON o.user_id = u.id")
+crs.execute("CREATE TABLE scratch (x INT)")
+crs.execute("SELECT COUNT(*) AS cnt, name n FROM users GROUP BY n ORDER BY cnt DESC")
+"#;
+        assert_eq!(check(code), vec![]);
+    }
+
+    #[test]
+    fn unknown_identifiers() {
+        let code = r#"
+crs.execute("SELECT nmae FROM user")
+crs.execute("""
+    SELECT u.id, o.totl
+    FROM users u, orders o
+    WHERE zzz = 1
+""")
+"#;
+        assert_eq!(
+            check(code),
+            vec![
+                (2, 31, "unknown table user, did you mean users?".into()),
+                (4, 18, "unknown column totl on table orders".into()),
+                (6, 11, "unknown column zzz".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use console::style;
+use serde::Serialize;
+
+use super::Program;
+use crate::cli::{Cli, DupesOptions, OutputFormat};
+use crate::error_exit;
+use crate::sql::{hash, skeleton};
+use crate::treesitter::{ts_query_factory, TreesitterQuery};
+use crate::utils::*;
+
+pub(crate) struct Dupes {
+    treesitter: Box<dyn TreesitterQuery>,
+    search_paths: Vec<PathBuf>,
+    format: OutputFormat,
+    similarity: f64,
+    min_tokens: usize,
+    variants: Vec<Variant>,
+    by_skeleton: HashMap<String, usize>,
+}
+
+/// Queries sharing the same skeleton.
+#[derive(Debug, Clone, Serialize)]
+struct Variant {
+    fingerprint: String,
+    sql: String,
+    locations: Vec<Location>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Location {
+    path: String,
+    line: usize,
+}
+
+/// Variants that are identical or close enough to count as the same query.
+#[derive(Debug, Serialize)]
+struct Cluster {
+    count: usize,
+    variants: Vec<Variant>,
+}
+
+impl Program for Dupes {
+    fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: DupesOptions = cli.command.into();
+
+        if !(0.0..=1.0).contains(&opts.similarity) {
+            error_exit!("--similarity must be between 0 and 1");
+        }
+
+        Self {
+            treesitter,
+            search_paths,
+            format: opts.format,
+            similarity: opts.similarity,
+            min_tokens: opts.min_tokens,
+            variants: Vec::new(),
+            by_skeleton: HashMap::new(),
+        }
+    }
+
+    fn run(&mut self) {
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (code, path) in iter_valid_files(&search_paths) {
+            self.add_file(&code, path.as_path().to_str().unwrap());
+        }
+
+        let clusters = self.clusters();
+        let result = match self.format {
+            OutputFormat::Text => {
+                print_text(&clusters);
+                Ok(())
+            }
+            OutputFormat::Csv => print_csv(&clusters),
+            OutputFormat::Json => print_json(&clusters),
+        };
+        if let Err(err) = result {
+            eprintln!("Failed to write duplicates: {err}");
+        }
+    }
+}
+
+impl Dupes {
+    fn add_file(&mut self, code: &str, path: &str) {
+        for block in self.treesitter.sql_blocks(code) {
+            let sql = skeleton(block.inner_text(code));
+            if sql.split(' ').count() < self.min_tokens {
+                continue;
+            }
+
+            let location = Location {
+                path: path.to_string(),
+                line: block.start_line_num(),
+            };
+            match self.by_skeleton.get(&sql) {
+                Some(&idx) => self.variants[idx].locations.push(location),
+                None => {
+                    self.by_skeleton.insert(sql.clone(), self.variants.len());
+                    self.variants.push(Variant {
+                        fingerprint: hash(&sql),
+                        sql,
+                        locations: vec![location],
+                    });
+                }
+            }
+        }
+    }
+
+    /// Group variants whose skeletons are at least `similarity` alike, keeping groups of more
+    /// than one query. Largest groups first.
+    fn clusters(&self) -> Vec<Cluster> {
+        let tokens = self
+            .variants
+            .iter()
+            .map(|variant| variant.sql.split(' ').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut parents = (0..self.variants.len()).collect::<Vec<_>>();
+        fn root(parents: &mut [usize], mut idx: usize) -> usize {
+            while parents[idx] != idx {
+                parents[idx] = parents[parents[idx]];
+                idx = parents[idx];
+            }
+            idx
+        }
+
+        if self.similarity < 1.0 {
+            for a in 0..tokens.len() {
+                for b in a + 1..tokens.len() {
+                    if similarity(&tokens[a], &tokens[b], self.similarity) {
+                        let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+                        parents[root_b] = root_a;
+                    }
+                }
+            }
+        }
+
+        let mut grouped: Vec<Vec<Variant>> = Vec::new();
+        let mut group_of_root = HashMap::new();
+        for (idx, variant) in self.variants.iter().enumerate() {
+            let root = root(&mut parents, idx);
+            let group = *group_of_root.entry(root).or_insert_with(|| {
+                grouped.push(Vec::new());
+                grouped.len() - 1
+            });
+            grouped[group].push(variant.clone());
+        }
+
+        let mut clusters = grouped
+            .into_iter()
+            .map(|mut variants| {
+                variants.sort_by_key(|variant| std::cmp::Reverse(variant.locations.len()));
+                Cluster {
+                    count: variants.iter().map(|variant| variant.locations.len()).sum(),
+                    variants,
+                }
+            })
+            .filter(|cluster| cluster.count > 1)
+            .collect::<Vec<_>>();
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.count));
+        clusters
+    }
+}
+
+/// Whether the token edit distance between `a` and `b` leaves them at least `threshold` alike.
+// `generic_levenshtein` takes a reference to a sized collection, so no slices here
+#[allow(clippy::ptr_arg)]
+fn similarity(a: &Vec<&str>, b: &Vec<&str>, threshold: f64) -> bool {
+    let longest = a.len().max(b.len()) as f64;
+    // the length difference alone is a lower bound of the distance
+    if 1.0 - (a.len().abs_diff(b.len()) as f64 / longest) < threshold {
+        return false;
+    }
+    1.0 - (strsim::generic_levenshtein(a, b) as f64 / longest) >= threshold
+}
+
+fn print_text(clusters: &[Cluster]) {
+    for cluster in clusters {
+        println!(
+            "{} ({} queries, {} variants)",
+            style(&cluster.variants[0].fingerprint).bold(),
+            cluster.count,
+            cluster.variants.len()
+        );
+        for variant in &cluster.variants {
+            println!("    {}", variant.sql);
+            for loc in &variant.locations {
+                println!(
+                    "        {}:{}",
+                    style(&loc.path).magenta(),
+                    style(loc.line).green()
+                );
+            }
+        }
+    }
+}
+
+fn print_csv(clusters: &[Cluster]) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    writer.write_record(["cluster", "fingerprint", "sql", "path", "line"])?;
+    for cluster in clusters {
+        for variant in &cluster.variants {
+            for loc in &variant.locations {
+                writer.write_record([
+                    &cluster.variants[0].fingerprint,
+                    &variant.fingerprint,
+                    &variant.sql,
+                    &loc.path,
+                    &loc.line.to_string(),
+                ])?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn print_json(clusters: &[Cluster]) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(std::io::stdout(), clusters)?;
+    println!();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treesitter::Exec;
+
+    fn dupes(code: &str, similarity: f64) -> Vec<Vec<String>> {
+        let mut dupes = Dupes {
+            treesitter: Box::new(Exec::new()),
+            search_paths: Vec::new(),
+            format: OutputFormat::Text,
+            similarity,
+            min_tokens: 5,
+            variants: Vec::new(),
+            by_skeleton: HashMap::new(),
+        };
+        dupes.add_file(code, "foo.py");
+        dupes
+            .clusters()
+            .into_iter()
+            .map(|cluster| {
+                cluster
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let lines = variant
+                            .locations
+                            .iter()
+                            .map(|loc| loc.line.to_string())
+                            .collect::<Vec<_>>();
+                        format!("{} @{}", variant.sql, lines.join(","))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clusters() {
+        let code = r#"
+crs.execute("SELECT id, name FROM users WHERE id = 1")
+crs.execute("""
+    select id, name
+    from users
+    where id = %s
+""", (id,))
+crs.execute(f"SELECT id, name FROM users WHERE id = {id} AND active = 1")
+crs.execute("DELETE FROM sessions WHERE expires < ?")
+crs.execute("SELECT 1")
+crs.execute("SELECT 1")
+"#;
+        assert_eq!(
+            dupes(code, 1.0),
+            vec![vec!["SELECT ID , NAME FROM USERS WHERE ID = ? @2,3"]]
+        );
+        assert_eq!(
+            dupes(code, 0.7),
+            vec![vec![
+                "SELECT ID , NAME FROM USERS WHERE ID = ? @2,3",
+                "SELECT ID , NAME FROM USERS WHERE ID = ? AND ACTIVE = ? @8",
+            ]]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use super::rg::utils::{block_lines, CodeDiff, MatchRange};
+use super::Program;
+use crate::cli::{Cli, ColumnUnit, ExplainOptions};
+use crate::error_exit;
+use crate::lint::{Finding, Severity};
+use crate::sql::lexer::tokenize;
+use crate::sql::{full_scans, split_statements, Explainer};
+use crate::treesitter::{ts_query_factory, TreesitterQuery};
+use crate::utils::*;
+
+const INVALID_RULE: &str = "invalid-sql";
+const SCAN_RULE: &str = "full-scan";
+
+pub(crate) struct Explain {
+    treesitter: Box<dyn TreesitterQuery>,
+    search_paths: Vec<PathBuf>,
+    column_unit: ColumnUnit,
+    explainer: Explainer,
+    explained: usize,
+    skipped: usize,
+}
+
+impl Program for Explain {
+    fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: ExplainOptions = cli.command.into();
+
+        let explainer = Explainer::open(&opts.sqlite).unwrap_or_else(|err| {
+            error_exit!("Failed to open database {}: {}", opts.sqlite.display(), err)
+        });
+
+        Self {
+            treesitter,
+            search_paths,
+            column_unit: cli.global.column_unit,
+            explainer,
+            explained: 0,
+            skipped: 0,
+        }
+    }
+
+    fn run(&mut self) {
+        let mut failed = false;
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (code, path) in iter_valid_files(&search_paths) {
+            let path = path.as_path().to_str().unwrap();
+            let lines = block_lines(&code);
+            for finding in self.explain_file(&code) {
+                let rng = MatchRange::from_abs_range(
+                    finding.range.clone(),
+                    &lines,
+                    &code,
+                    self.column_unit,
+                );
+                let line =
+                    CodeDiff::new_line(&code, &rng).with_diff_color(finding.severity.color());
+                print(
+                    path,
+                    rng.start_point.row + 1,
+                    Some(rng.column),
+                    &format!("{}\n    {}", finding.display(), line.trim()),
+                );
+                failed |= finding.severity == Severity::Error;
+            }
+        }
+
+        eprintln!(
+            "Explained {} statements, skipped {} that weren't queries or couldn't be bound",
+            self.explained, self.skipped
+        );
+        if failed {
+            std::process::exit(1);
+        }
+    }
+}
+
+impl Explain {
+    fn explain_file(&mut self, code: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for block in self.treesitter.sql_blocks(code) {
+            let offset = block.inner_text_range().start;
+            let sql = block.inner_text(code);
+            for range in split_statements(sql) {
+                let statement = &sql[range.clone()];
+                let Some(plan) = self.explainer.explain(statement) else {
+                    self.skipped += 1;
+                    continue;
+                };
+                self.explained += 1;
+
+                // point at the statement's first token, or at the table scanned
+                let first = tokenize(statement)[0].range.clone();
+                let abs_range = |rng: Range<usize>| {
+                    offset + range.start + rng.start..offset + range.start + rng.end
+                };
+                match plan {
+                    Err(err) => findings.push(Finding {
+                        rule: INVALID_RULE,
+                        severity: Severity::Error,
+                        range: abs_range(first),
+                        message: err.to_string(),
+                    }),
+                    Ok(plan) => {
+                        for table in full_scans(&plan) {
+                            let table_range = tokenize(statement)
+                                .into_iter()
+                                .find(|tok| {
+                                    tok.is_identifier()
+                                        && tok.unquoted().eq_ignore_ascii_case(table)
+                                })
+                                .map_or(first.clone(), |tok| tok.range);
+                            findings.push(Finding {
+                                rule: SCAN_RULE,
+                                severity: Severity::Warning,
+                                range: abs_range(table_range),
+                                message: format!("full table scan of `{table}`"),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treesitter::Exec;
+
+    #[test]
+    fn findings() {
+        let path =
+            std::env::temp_dir().join(format!("sql-parse-explain-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);")
+                .unwrap();
+        }
+
+        let mut explain = Explain {
+            treesitter: Box::new(Exec::new()),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            explainer: Explainer::open(&path).unwrap(),
+            explained: 0,
+            skipped: 0,
+        };
+        let code = r#"
+crs.execute("SELECT name FROM users WHERE id = ?", (1,))
+crs.execute("SELECT id FROM users WHERE name = %s", (name,))
+crs.execute("SELECT * FROM missing")
+crs.execute(f"SELECT * FROM {table}")
+crs.execute("CREATE TABLE other (id INT); DELETE FROM users WHERE id = :id", {"id": 1})
+"#;
+        let findings = explain
+            .explain_file(code)
+            .into_iter()
+            .map(|finding| {
+                let (line, col) = line_col(code, finding.range.start, ColumnUnit::Bytes);
+                format!(
+                    "{line}:{col} {}",
+                    console::strip_ansi_codes(&finding.display())
+                )
+            })
+            .collect::<Vec<_>>();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            findings,
+            vec![
+                "3:29 warning[full-scan] full table scan of `users`",
+                "4:14 error[invalid-sql] no such table: missing",
+            ]
+        );
+        assert_eq!((explain.explained, explain.skipped), (4, 2));
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::rg::utils::apply_edits;
+use super::Program;
+use crate::cli::{Cli, ColumnUnit, ExtractOptions};
+use crate::treesitter::{enclosing_function, new_parser, parser_tree, ts_query_factory};
+use crate::treesitter::{SqlBlock, TreesitterQuery};
+use crate::utils::*;
+
+pub(crate) struct Extract {
+    treesitter: Box<dyn TreesitterQuery>,
+    search_paths: Vec<PathBuf>,
+    column_unit: ColumnUnit,
+    backup: Option<String>,
+    dir: PathBuf,
+    name: String,
+    loader: String,
+    min_lines: usize,
+}
+
+/// A string to move into its own `.sql` file.
+#[derive(Debug, PartialEq)]
+struct Extraction {
+    name: String,
+    /// The whole Python literal, quotes and prefix included.
+    literal: Range<usize>,
+    sql: String,
+}
+
+impl Program for Extract {
+    fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: ExtractOptions = cli.command.into();
+
+        Self {
+            treesitter,
+            search_paths,
+            column_unit: cli.global.column_unit,
+            backup: cli.global.backup,
+            dir: opts.dir,
+            name: opts.name,
+            loader: opts.loader,
+            min_lines: opts.min_lines,
+        }
+    }
+
+    fn run(&mut self) {
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (mut code, path) in iter_valid_files(&search_paths) {
+            let mut edits = Vec::new();
+            let mut created = Vec::new();
+            for extraction in self.extractions(&code, path) {
+                let sql_path = self.dir.join(format!("{}.sql", extraction.name));
+                match write_sql(&sql_path, &extraction.sql) {
+                    Ok(true) => created.push(sql_path),
+                    Ok(false) => {}
+                    Err(err) => {
+                        let (line, col) =
+                            line_col(&code, extraction.literal.start, self.column_unit);
+                        eprintln!(
+                            "{}:{line}:{col}: not extracted to {}: {err}",
+                            path.display(),
+                            sql_path.display()
+                        );
+                        continue;
+                    }
+                }
+                let call = self.loader.replace("{name}", &extraction.name);
+                edits.push((extraction.literal, call));
+            }
+
+            if edits.is_empty() {
+                continue;
+            }
+            let change_count = edits.len();
+            apply_edits(&mut code, edits);
+            if write_code(path, &code, self.backup.as_deref()).is_err() {
+                eprintln!("Failed to write to path: {}", path.display());
+                // the queries would be left without the code loading them
+                for sql_path in created {
+                    let _ = std::fs::remove_file(sql_path);
+                }
+                continue;
+            }
+            println!("{change_count} queries extracted from {}", path.display());
+        }
+    }
+}
+
+impl Extract {
+    /// Strings in `code` that can be moved to a `.sql` file as is, named after the template.
+    fn extractions(&mut self, code: &str, path: &Path) -> Vec<Extraction> {
+        let tree = parser_tree(&mut new_parser(), code);
+        let file = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+
+        let mut extractions = Vec::new();
+        for block in self.treesitter.sql_blocks(code) {
+            let literal = block.string_start.byte_range.start..block.string_end.byte_range.end;
+            let lines = line_number(code, literal.end) - block.string_start.point.row;
+            if lines < self.min_lines {
+                continue;
+            }
+            if let Err(reason) = extractable(code, &block) {
+                let (line, col) = line_col(code, literal.start, self.column_unit);
+                eprintln!("{}:{line}:{col}: not extracted, {reason}", path.display());
+                continue;
+            }
+            let concatenated = tree
+                .root_node()
+                .descendant_for_byte_range(literal.start, literal.end)
+                .and_then(|node| node.parent())
+                .is_some_and(|parent| parent.kind() == "concatenated_string");
+            if concatenated {
+                let (line, col) = line_col(code, literal.start, self.column_unit);
+                eprintln!(
+                    "{}:{line}:{col}: not extracted, implicitly concatenated strings are \
+                     extracted on their own",
+                    path.display()
+                );
+                continue;
+            }
+
+            let function = enclosing_function(&tree, code, literal.start).unwrap_or("module");
+            let index = counts.entry(function).or_default();
+            *index += 1;
+            let name = self
+                .name
+                .replace("{file}", &file)
+                .replace("{function}", function)
+                .replace("{index}", &index.to_string());
+
+            let sql = textwrap::dedent(block.inner_text(code));
+            extractions.push(Extraction {
+                name,
+                literal,
+                sql: format!("{}\n", sql.trim()),
+            });
+        }
+        extractions
+    }
+}
+
+/// Whether the text between the quotes of `block` is the SQL itself, with no escapes or
+/// interpolation to evaluate first.
+fn extractable(code: &str, block: &SqlBlock) -> Result<(), &'static str> {
+    let (prefix, _) = block
+        .opening(code)
+        .ok_or("the string prefix isn't valid Python")?;
+    let sql = block.inner_text(code);
+
+    if prefix.bytes {
+        Err("bytes literals aren't SQL text")
+    } else if prefix.format && sql.contains(['{', '}']) {
+        Err("f-strings are built at runtime")
+    } else if !prefix.raw && sql.contains('\\') {
+        Err("escape sequences would be written as is")
+    } else {
+        Ok(())
+    }
+}
+
+/// Write `sql` to `path`, leaving an existing file alone when it holds the same query. Returns
+/// whether the file was created.
+fn write_sql(path: &Path, sql: &str) -> anyhow::Result<bool> {
+    match std::fs::read_to_string(path) {
+        Ok(existing) if existing == sql => return Ok(false),
+        Ok(_) => anyhow::bail!("file exists with a different query"),
+        Err(_) => {}
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    rewrite_file(path, None, sql.as_bytes(), None)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treesitter::Exec;
+
+    #[test]
+    fn extractions() {
+        let code = r#"
+crs.execute("SELECT 1")
+
+def get_user(crs, id):
+    crs.execute("""
+        SELECT name
+        FROM users
+        WHERE id = ?
+    """, (id,))
+    crs.execute(f"SELECT * FROM {table}")
+    crs.execute(r"SELECT '\d'")
+    crs.execute("SELECT '\n'")
+    crs.execute("SELECT 1 " "FROM foo")
+"#;
+        let mut extract = Extract {
+            treesitter: Box::new(Exec::new()),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            backup: None,
+            dir: PathBuf::from("sql"),
+            name: "{file}_{function}_{index}".to_string(),
+            loader: "load_sql(\"{name}\")".to_string(),
+            min_lines: 1,
+        };
+        let extractions = extract
+            .extractions(code, Path::new("app/users.py"))
+            .into_iter()
+            .map(|e| (e.name, &code[e.literal], e.sql))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            extractions,
+            vec![
+                (
+                    "users_module_1".to_string(),
+                    "\"SELECT 1\"",
+                    "SELECT 1\n".to_string()
+                ),
+                (
+                    "users_get_user_1".to_string(),
+                    "\"\"\"\n        SELECT name\n        FROM users\n        WHERE id = ?\n    \"\"\"",
+                    "SELECT name\nFROM users\nWHERE id = ?\n".to_string()
+                ),
+                (
+                    "users_get_user_2".to_string(),
+                    "r\"SELECT '\\d'\"",
+                    "SELECT '\\d'\n".to_string()
+                ),
+            ]
+        );
+
+        extract.min_lines = 2;
+        let names = extract
+            .extractions(code, Path::new("app/users.py"))
+            .into_iter()
+            .map(|e| e.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["users_get_user_1"]);
+    }
+}
@@ -0,0 +1,227 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use tree_sitter::Node;
+
+use super::rg::utils::apply_edits;
+use super::Program;
+use crate::cli::{Cli, ColumnUnit, InlineOptions};
+use crate::error_exit;
+use crate::treesitter::{new_parser, parser_tree};
+use crate::utils::*;
+
+const TRIPLE_QUOTES: &str = "\"\"\"";
+
+pub(crate) struct Inline {
+    search_paths: Vec<PathBuf>,
+    column_unit: ColumnUnit,
+    backup: Option<String>,
+    dir: PathBuf,
+    loader: Regex,
+    max_lines: Option<usize>,
+}
+
+/// A loader call and the file it loads.
+#[derive(Debug, PartialEq)]
+struct LoaderCall {
+    range: Range<usize>,
+    name: String,
+}
+
+impl Program for Inline {
+    fn new(cli: Cli) -> Self {
+        let search_paths = cli.search_paths();
+        let opts: InlineOptions = cli.command.into();
+
+        let dir = opts.dir.unwrap_or_else(|| match opens_file(&opts.loader) {
+            true => PathBuf::from("."),
+            false => PathBuf::from("sql"),
+        });
+
+        Self {
+            search_paths,
+            column_unit: cli.global.column_unit,
+            backup: cli.global.backup,
+            dir,
+            loader: loader_regex(&opts.loader)
+                .unwrap_or_else(|| error_exit!("--loader must contain `{{name}}`")),
+            max_lines: opts.max_lines,
+        }
+    }
+
+    fn run(&mut self) {
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (mut code, path) in iter_valid_files(&search_paths) {
+            let mut edits = Vec::new();
+            for call in self.loader_calls(&code) {
+                match self.literal(&code, &call) {
+                    Ok(Some(literal)) => edits.push((call.range, literal)),
+                    Ok(None) => {}
+                    Err(reason) => {
+                        let (line, col) = line_col(&code, call.range.start, self.column_unit);
+                        eprintln!("{}:{line}:{col}: not inlined, {reason}", path.display());
+                    }
+                }
+            }
+
+            if edits.is_empty() {
+                continue;
+            }
+            let change_count = edits.len();
+            apply_edits(&mut code, edits);
+            if write_code(path, &code, self.backup.as_deref()).is_err() {
+                eprintln!("Failed to write to path: {}", path.display());
+            }
+            println!("{change_count} queries inlined into {}", path.display());
+        }
+    }
+}
+
+impl Inline {
+    /// Outermost calls in `code` matching the loader template.
+    fn loader_calls(&self, code: &str) -> Vec<LoaderCall> {
+        fn visit(node: Node, code: &str, loader: &Regex, calls: &mut Vec<LoaderCall>) {
+            if node.kind() == "call" {
+                if let Some(caps) = loader.captures(&code[node.byte_range()]) {
+                    calls.push(LoaderCall {
+                        range: node.byte_range(),
+                        name: caps[1].to_string(),
+                    });
+                    return;
+                }
+            }
+            let mut tc = node.walk();
+            for child in node.children(&mut tc) {
+                visit(child, code, loader, calls);
+            }
+        }
+
+        let tree = parser_tree(&mut new_parser(), code);
+        let mut calls = Vec::new();
+        visit(tree.root_node(), code, &self.loader, &mut calls);
+        calls
+    }
+
+    /// The `"""` string replacing `call`, indented to match the line it's on. `None` when the file
+    /// is longer than `--max-lines`.
+    fn literal(&self, code: &str, call: &LoaderCall) -> anyhow::Result<Option<String>> {
+        let mut path = self.dir.join(&call.name);
+        if path.extension().is_none() {
+            path.set_extension("sql");
+        }
+        let sql = read_sql(&path)?;
+        if self.max_lines.is_some_and(|max| sql.lines().count() > max) {
+            return Ok(None);
+        }
+
+        let line_start = code[..call.range.start]
+            .rfind('\n')
+            .map_or(0, |idx| idx + 1);
+        let before_call = &code[line_start..call.range.start];
+        let indent = &before_call[..before_call.len() - before_call.trim_start().len()];
+        let body = sql
+            .lines()
+            .map(|line| {
+                if line.trim().is_empty() {
+                    String::new()
+                } else {
+                    format!("{indent}    {line}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(Some(format!(
+            "{TRIPLE_QUOTES}\n{body}\n{indent}{TRIPLE_QUOTES}"
+        )))
+    }
+}
+
+/// A regex matching the whole text of a loader call, capturing the file name. Either quote style
+/// matches the template's quotes. `None` when the template has no `{name}`.
+fn loader_regex(template: &str) -> Option<Regex> {
+    let (before, after) = template.split_once("{name}")?;
+    let part = |text: &str| regex::escape(text).replace(['"', '\''], "[\"']");
+    let pattern = format!(r#"^{}([^"'\\\n]+){}$"#, part(before), part(after));
+    Some(Regex::new(&pattern).unwrap_or_else(|_| error_exit!("Invalid --loader {}", template)))
+}
+
+/// Whether `template` opens the file itself, taking a path relative to the working directory.
+fn opens_file(template: &str) -> bool {
+    template.trim_start().starts_with("open(")
+}
+
+/// SQL to inline from `path`, refusing anything a plain `"""` string can't hold as is.
+fn read_sql(path: &Path) -> anyhow::Result<String> {
+    let sql = std::fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("failed to read {}: {err}", path.display()))?;
+    let sql = sql.trim_matches('\n');
+    if sql.contains('\\') {
+        anyhow::bail!("{} contains backslashes", path.display());
+    }
+    if sql.contains(TRIPLE_QUOTES) || sql.ends_with('"') {
+        anyhow::bail!("{} contains quotes ending the string", path.display());
+    }
+    Ok(sql.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn loader_template() {
+        let re = loader_regex(r#"open("{name}").read()"#).unwrap();
+        assert_eq!(
+            &re.captures("open('q/users.sql').read()").unwrap()[1],
+            "q/users.sql"
+        );
+        assert!(re.captures(r#"open("users.sql")"#).is_none());
+        assert!(loader_regex("load_sql()").is_none());
+        assert!(opens_file(r#"open("{name}").read()"#));
+        assert!(!opens_file(r#"load_sql("{name}")"#));
+    }
+
+    #[test]
+    fn inline_calls() {
+        let dir = std::env::temp_dir().join(format!("sql-parse-inline-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("users.sql"),
+            "SELECT name\nFROM users\n\nWHERE id = ?\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("escaped.sql"), "SELECT '\\n'\n").unwrap();
+
+        let inline = Inline {
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            backup: None,
+            dir: dir.clone(),
+            loader: loader_regex(r#"load_sql("{name}")"#).unwrap(),
+            max_lines: None,
+        };
+        let code = r#"
+def get_user(crs, id):
+    crs.execute(load_sql("users"), (id,))
+    crs.execute(load_sql('escaped'))
+    other(load_sql(name))
+"#;
+        let calls = inline.loader_calls(code);
+        let literals = calls
+            .iter()
+            .map(|call| inline.literal(code, call).map_err(|err| err.to_string()))
+            .collect::<Vec<_>>();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(
+            literals[0].as_ref().unwrap().as_deref(),
+            Some("\"\"\"\n        SELECT name\n        FROM users\n\n        WHERE id = ?\n    \"\"\"")
+        );
+        assert!(literals[1]
+            .as_ref()
+            .unwrap_err()
+            .ends_with("contains backslashes"));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use console::style;
+use serde::Serialize;
+
+use super::Program;
+use crate::cli::{Cli, InventoryOptions, OutputFormat};
+use crate::sql::{references, Access, RefKind};
+use crate::treesitter::{ts_query_factory, TreesitterQuery};
+use crate::utils::*;
+
+pub(crate) struct Inventory {
+    treesitter: Box<dyn TreesitterQuery>,
+    search_paths: Vec<PathBuf>,
+    format: OutputFormat,
+    entries: BTreeMap<EntryKey, Entry>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct EntryKey {
+    table: String,
+    column: String,
+    kind: RefKind,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry {
+    kind: RefKind,
+    table: Option<String>,
+    column: Option<String>,
+    locations: Vec<Location>,
+}
+
+impl Entry {
+    fn display_name(&self) -> String {
+        match (&self.table, &self.column) {
+            (Some(table), Some(column)) => format!("{table}.{column}"),
+            (None, Some(column)) => column.to_string(),
+            (Some(table), None) => table.to_string(),
+            (None, None) => unreachable!("inventory entry without a name"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Location {
+    access: Access,
+    path: String,
+    line: usize,
+}
+
+impl Program for Inventory {
+    fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: InventoryOptions = cli.command.into();
+
+        Self {
+            treesitter,
+            search_paths,
+            format: opts.format,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn run(&mut self) {
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (code, path) in iter_valid_files(&search_paths) {
+            self.add_file(&code, path.as_path().to_str().unwrap());
+        }
+
+        let result = match self.format {
+            OutputFormat::Text => {
+                self.print_text();
+                Ok(())
+            }
+            OutputFormat::Csv => self.print_csv(),
+            OutputFormat::Json => self.print_json(),
+        };
+        if let Err(err) = result {
+            eprintln!("Failed to write inventory: {err}");
+        }
+    }
+}
+
+impl Inventory {
+    fn add_file(&mut self, code: &str, path: &str) {
+        for block in self.treesitter.sql_blocks(code) {
+            let offset = block.inner_text_range().start;
+            for reference in references(block.inner_text(code)) {
+                let (table, column) = match reference.kind {
+                    RefKind::Table => (Some(reference.name), None),
+                    RefKind::Column => (reference.table, Some(reference.name)),
+                };
+                let key = EntryKey {
+                    table: table.as_deref().unwrap_or_default().to_lowercase(),
+                    column: column.as_deref().unwrap_or_default().to_lowercase(),
+                    kind: reference.kind,
+                };
+
+                self.entries
+                    .entry(key)
+                    .or_insert_with(|| Entry {
+                        kind: reference.kind,
+                        table,
+                        column,
+                        locations: Vec::new(),
+                    })
+                    .locations
+                    .push(Location {
+                        access: reference.access,
+                        path: path.to_string(),
+                        line: line_number(code, offset + reference.range.start),
+                    });
+            }
+        }
+    }
+
+    fn print_text(&self) {
+        for entry in self.entries.values() {
+            let kind = match entry.kind {
+                RefKind::Table => "table",
+                RefKind::Column => "column",
+            };
+            println!("{} ({kind})", style(entry.display_name()).bold());
+            for loc in &entry.locations {
+                println!(
+                    "    {:<6} {}:{}",
+                    loc.access,
+                    style(&loc.path).magenta(),
+                    style(loc.line).green()
+                );
+            }
+        }
+    }
+
+    fn print_csv(&self) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        writer.write_record(["kind", "table", "column", "access", "path", "line"])?;
+        for entry in self.entries.values() {
+            for loc in &entry.locations {
+                writer.write_record([
+                    match entry.kind {
+                        RefKind::Table => "table",
+                        RefKind::Column => "column",
+                    },
+                    entry.table.as_deref().unwrap_or_default(),
+                    entry.column.as_deref().unwrap_or_default(),
+                    &loc.access.to_string(),
+                    &loc.path,
+                    &loc.line.to_string(),
+                ])?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn print_json(&self) -> anyhow::Result<()> {
+        let entries = self.entries.values().collect::<Vec<_>>();
+        serde_json::to_writer_pretty(std::io::stdout(), &entries)?;
+        println!();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treesitter::Exec;
+
+    fn inventory(code: &str) -> Inventory {
+        let mut inventory = Inventory {
+            treesitter: Box::new(Exec::new()),
+            search_paths: Vec::new(),
+            format: OutputFormat::Text,
+            entries: BTreeMap::new(),
+        };
+        inventory.add_file(code, "foo.py");
+        inventory
+    }
+
+    #[test]
+    fn aggregates_locations() {
+        let code = r#"
+crs.execute("SELECT name FROM users")
+crs.execute("""
+    UPDATE Users
+    SET name = %s
+""", (name,))
+"#;
+        let inventory = inventory(code);
+        let summary = inventory
+            .entries
+            .values()
+            .map(|entry| {
+                let locs = entry
+                    .locations
+                    .iter()
+                    .map(|loc| format!("{}@{}", loc.access, loc.line))
+                    .collect::<Vec<_>>();
+                format!("{} {}", entry.display_name(), locs.join(","))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec!["users read@2,write@4", "users.name read@2,write@5"]
+        );
+    }
+
+    #[test]
+    fn json_output() {
+        let inventory = inventory(r#"crs.execute("DELETE FROM t")"#);
+        let entries = inventory.entries.values().collect::<Vec<_>>();
+        assert_eq!(
+            serde_json::to_string(&entries).unwrap(),
+            r#"[{"kind":"table","table":"t","column":null,"locations":[{"access":"write","path":"foo.py","line":1}]}]"#
+        );
+    }
+}
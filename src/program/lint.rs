@@ -0,0 +1,299 @@
+use std::path::PathBuf;
+
+use super::rg::utils::{block_lines, CodeDiff, MatchRange};
+use super::Program;
+use crate::cli::{Cli, ColumnUnit, LintOptions};
+use crate::error_exit;
+use crate::lint::{BaselineMode, Config, Finding, Linter, Severity, Suppressions};
+use crate::sql::fingerprint;
+use crate::treesitter::Exec;
+use crate::utils::*;
+
+pub(crate) struct Lint {
+    exec: Exec,
+    linter: Linter,
+    search_paths: Vec<PathBuf>,
+    column_unit: ColumnUnit,
+    report_unused_ignores: bool,
+    baseline: BaselineMode,
+}
+
+impl Program for Lint {
+    fn new(cli: Cli) -> Self {
+        let search_paths = cli.search_paths();
+        let opts: LintOptions = cli.command.into();
+
+        let mut config = match opts.config.or_else(Config::find) {
+            Some(path) => Config::load(&path).unwrap_or_else(|err| {
+                error_exit!("Failed to load config {}: {}", path.display(), err)
+            }),
+            None => Config::default(),
+        };
+        if !opts.select.is_empty() {
+            config.select = Some(opts.select);
+        }
+        config.ignore.extend(opts.ignore);
+        if opts.paramstyle.is_some() {
+            config.paramstyle = opts.paramstyle;
+        }
+
+        Self {
+            exec: Exec::new(),
+            linter: Linter::new(&config).unwrap_or_else(|err| error_exit!("{}", err)),
+            search_paths,
+            column_unit: cli.global.column_unit,
+            report_unused_ignores: opts.report_unused_ignores,
+            baseline: BaselineMode::new(opts.baseline, opts.write_baseline),
+        }
+    }
+
+    fn run(&mut self) {
+        let mut failed = false;
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (code, path) in iter_valid_files(&search_paths) {
+            let path = path.as_path().to_str().unwrap();
+            let lines = block_lines(&code);
+            for finding in self.lint_file(&code, path) {
+                let rng = MatchRange::from_abs_range(
+                    finding.range.clone(),
+                    &lines,
+                    &code,
+                    self.column_unit,
+                );
+                let line =
+                    CodeDiff::new_line(&code, &rng).with_diff_color(finding.severity.color());
+                print(
+                    path,
+                    rng.start_point.row + 1,
+                    Some(rng.column),
+                    &format!("{}\n    {}", finding.display(), line.trim()),
+                );
+                failed |= finding.severity > Severity::Info;
+            }
+        }
+
+        self.baseline.finish();
+        if failed {
+            std::process::exit(1);
+        }
+    }
+}
+
+impl Lint {
+    fn lint_file(&mut self, code: &str, path: &str) -> Vec<Finding> {
+        let calls = self.exec.calls(code);
+        let blocks = calls
+            .iter()
+            .flat_map(|call| call.blocks.iter().cloned())
+            .collect::<Vec<_>>();
+        let mut suppressions = Suppressions::new(code, &blocks);
+
+        let mut findings = Vec::new();
+        for call in &calls {
+            let sql = call
+                .blocks
+                .iter()
+                .map(|block| block.inner_text(code))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let fingerprint = fingerprint(&sql);
+            findings.extend(self.linter.check(call, code).into_iter().filter(|finding| {
+                !suppressions.suppresses(code, call.call.byte_range.clone(), finding.rule)
+                    && self.baseline.report(path, &fingerprint, finding.rule)
+            }));
+        }
+        if self.report_unused_ignores {
+            findings.extend(suppressions.unused(&self.linter.rule_ids()));
+            findings.sort_by_key(|finding| finding.range.start);
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::ParamStyle;
+
+    fn select(rules: &[&str]) -> Config {
+        Config {
+            select: Some(rules.iter().map(|rule| rule.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    fn lint(code: &str, config: Config) -> Vec<String> {
+        let mut lint = Lint {
+            exec: Exec::new(),
+            linter: Linter::new(&config).unwrap(),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            report_unused_ignores: true,
+            baseline: BaselineMode::Off,
+        };
+        lint.lint_file(code, "foo.py")
+            .into_iter()
+            .map(|finding| {
+                let (line, col) = line_col(code, finding.range.start, ColumnUnit::Bytes);
+                format!(
+                    "{line}:{col} {}",
+                    console::strip_ansi_codes(&finding.display())
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn findings() {
+        let code = r#"
+QUERY_LIMIT = 10
+
+def search(term):
+    crs.execute(f"SELECT * FROM t WHERE x = '{term}' LIMIT {QUERY_LIMIT}")
+    crs.execute("SELECT * FROM t WHERE x = %s", (term,))
+"#;
+        assert_eq!(
+            lint(code, select(&["sql-injection"])),
+            vec![
+                "5:47 warning[sql-injection] SQL built with f-string interpolation of `term` (function parameter); pass it as a bound parameter",
+                "5:61 info[sql-injection] SQL built with f-string interpolation of `QUERY_LIMIT` (constant); pass it as a bound parameter",
+            ]
+        );
+    }
+
+    #[test]
+    fn param_counts() {
+        let code = r#"
+crs.execute("SELECT * FROM t WHERE id = ? AND x = ?", (a,))
+crs.execute("SELECT * FROM t WHERE id = ?")
+crs.execute("SELECT * FROM t WHERE id = %(id)s AND x = %(x)s", {"id": 1, "y": 2})
+crs.execute("SELECT * FROM t WHERE id = $1 AND x = $2 OR y = $1", [a, b])
+crs.execute("SELECT * FROM t", (a,))
+crs.execute("SELECT * FROM t WHERE id = :id", (a,))
+crs.execute("SELECT * FROM t WHERE id = ? AND x = %s", (a, b))
+crs.execute("SELECT * FROM t WHERE id = ?", args)
+"#;
+        assert_eq!(
+            lint(code, select(&["param-count", "paramstyle"])),
+            vec![
+                "2:55 error[param-count] SQL has 2 placeholders but 1 parameter passed",
+                "3:13 error[param-count] SQL has 1 placeholder but no parameters are passed",
+                "4:56 error[param-count] no parameter passed for `x`",
+                "4:64 warning[param-count] parameter `y` isn't used by the SQL",
+                "6:32 error[param-count] 1 parameter passed but the SQL has no placeholders",
+                "7:47 error[param-count] named placeholders take a dict of parameters, not a sequence",
+                "8:51 warning[paramstyle] `%s` mixes the format paramstyle into a qmark query",
+            ]
+        );
+
+        assert_eq!(
+            lint(
+                r#"crs.execute("SELECT * FROM t WHERE id = ?", (a,))"#,
+                Config {
+                    paramstyle: Some(ParamStyle::Format),
+                    ..select(&["param-count", "paramstyle"])
+                }
+            ),
+            vec![
+                "1:41 warning[paramstyle] `?` is a qmark placeholder but the paramstyle is format"
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_rules() {
+        let code = r#"
+crs.execute("SELECT * FROM a, b WITH (NOLOCK) ORDER BY 1")
+crs.execute("DELETE FROM a")
+"#;
+        assert_eq!(
+            lint(code, Config::default()),
+            vec![
+                "2:21 warning[select-star] `SELECT *` breaks when columns change; list them",
+                "2:31 warning[implicit-cross-join] implicit cross join; use an explicit `JOIN ... ON`",
+                "2:39 warning[nolock] `NOLOCK` reads uncommitted data and can return rows twice or not at all",
+                "2:56 warning[order-by-ordinal] `ORDER BY 1` refers to a column by position; name the column",
+                "3:14 error[missing-where] `DELETE` without `WHERE` affects every row",
+            ]
+        );
+
+        let config: Config = toml::from_str(
+            r#"
+ignore = ["nolock", "implicit-cross-join"]
+
+[rules.select-star]
+severity = "info"
+
+[rules.missing-where]
+statements = ["UPDATE"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            lint(code, config),
+            vec![
+                "2:21 info[select-star] `SELECT *` breaks when columns change; list them",
+                "2:56 warning[order-by-ordinal] `ORDER BY 1` refers to a column by position; name the column",
+            ]
+        );
+
+        assert!(Linter::new(&select(&["no-such-rule"])).is_err());
+    }
+
+    #[test]
+    fn suppressions() {
+        let code = r#"
+crs.execute("DELETE FROM a")  # sql-parse: ignore[missing-where]
+# sql-parse: ignore[select-star, nolock]
+crs.execute("SELECT * FROM a")
+crs.execute("""
+    -- sql-parse: ignore
+    DELETE FROM b
+""")
+crs.execute("SELECT id FROM a")  # sql-parse: ignore
+"#;
+        assert_eq!(
+            lint(code, Config::default()),
+            vec![
+                "3:1 warning[unused-ignore] unused `sql-parse: ignore` for `nolock`",
+                "9:34 warning[unused-ignore] unused `sql-parse: ignore`",
+            ]
+        );
+    }
+
+    #[test]
+    fn baseline() {
+        let mut lint = Lint {
+            exec: Exec::new(),
+            linter: Linter::new(&select(&["select-star", "missing-where"])).unwrap(),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            report_unused_ignores: false,
+            baseline: BaselineMode::Write("baseline.json".into(), Default::default()),
+        };
+        let old = r#"
+crs.execute("SELECT * FROM a")
+crs.execute("DELETE FROM a")
+"#;
+        assert_eq!(lint.lint_file(old, "foo.py"), vec![]);
+
+        let BaselineMode::Write(_, baseline) = lint.baseline else {
+            unreachable!()
+        };
+        lint.baseline = BaselineMode::Filter(baseline);
+        let new = r#"
+crs.execute("DELETE FROM b")
+
+crs.execute("""
+    select *
+    from a
+""")
+"#;
+        let rules = lint
+            .lint_file(new, "foo.py")
+            .into_iter()
+            .map(|finding| finding.rule)
+            .collect::<Vec<_>>();
+        assert_eq!(rules, vec!["missing-where"]);
+    }
+}
@@ -1,10 +1,32 @@
+mod check;
+mod dupes;
+mod explain;
+mod extract;
+mod inline;
+mod inventory;
+mod lint;
+mod parameterize;
 mod quotes;
+mod rename;
 mod rg;
+mod stats;
 mod treesitter;
+mod undo;
 
+use check::Check;
+use dupes::Dupes;
+use explain::Explain;
+use extract::Extract;
+use inline::Inline;
+use inventory::Inventory;
+use lint::Lint;
+use parameterize::Parameterize;
 use quotes::Quotes;
+use rename::Rename;
 use rg::Rg;
+use stats::Stats;
 use treesitter::Treesitter;
+use undo::Undo;
 
 use crate::cli::{Cli, Commands};
 
@@ -21,5 +43,16 @@ pub fn new_program(cli: Cli) -> Box<dyn Program> {
         Commands::TS(_) => Box::new(Treesitter::new(cli)),
         Commands::Quotes(_) => Box::new(Quotes::new(cli)),
         Commands::Regex(_) => Box::new(Rg::new(cli)),
+        Commands::Inventory(_) => Box::new(Inventory::new(cli)),
+        Commands::Check(_) => Box::new(Check::new(cli)),
+        Commands::Lint(_) => Box::new(Lint::new(cli)),
+        Commands::Rename(_) => Box::new(Rename::new(cli)),
+        Commands::Dupes(_) => Box::new(Dupes::new(cli)),
+        Commands::Stats(_) => Box::new(Stats::new(cli)),
+        Commands::Explain(_) => Box::new(Explain::new(cli)),
+        Commands::Extract(_) => Box::new(Extract::new(cli)),
+        Commands::Inline(_) => Box::new(Inline::new(cli)),
+        Commands::Parameterize(_) => Box::new(Parameterize::new(cli)),
+        Commands::Undo => Box::new(Undo::new(cli)),
     }
 }
@@ -0,0 +1,748 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use tree_sitter::Node;
+
+use super::rg::utils::apply_edits;
+use super::Program;
+use crate::cli::{Cli, ColumnUnit, ParamStyle, ParameterizeOptions};
+use crate::error_exit;
+use crate::lint::Config;
+use crate::sql::{placeholders, value_slot, Placeholder, Slot};
+use crate::treesitter::{new_parser, parser_tree, Exec, ExecCall, ParamsShape, SqlBlock};
+use crate::utils::*;
+
+pub(crate) struct Parameterize {
+    exec: Exec,
+    search_paths: Vec<PathBuf>,
+    column_unit: ColumnUnit,
+    backup: Option<String>,
+    paramstyle: Option<ParamStyle>,
+}
+
+/// How values are spliced into the SQL string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Form {
+    FString,
+    Format,
+    Percent,
+}
+
+/// A value spliced into the SQL string.
+#[derive(Debug)]
+struct Field {
+    /// The field in the string, e.g. `{uid}` or `%s`.
+    range: Range<usize>,
+    /// Python source of the value. `None` when the field does more than insert it, e.g. has a
+    /// format spec.
+    expr: Option<String>,
+    /// The name of keyword `.format()` arguments and `%(name)s` fields.
+    key: Option<String>,
+}
+
+/// How values are spliced in, the string holding the SQL and its fields.
+type Spliced<'t> = (Form, Node<'t>, Vec<Field>);
+
+/// Edits parameterizing an `execute` call and the values left alone, with why.
+#[derive(Debug, Default)]
+struct Rewrite {
+    edits: Vec<(Range<usize>, String)>,
+    skipped: Vec<(usize, String)>,
+    queries: usize,
+}
+
+impl Program for Parameterize {
+    fn new(cli: Cli) -> Self {
+        let search_paths = cli.search_paths();
+        let opts: ParameterizeOptions = cli.command.into();
+
+        let config = match opts.config.or_else(Config::find) {
+            Some(path) => Config::load(&path).unwrap_or_else(|err| {
+                error_exit!("Failed to load config {}: {}", path.display(), err)
+            }),
+            None => Config::default(),
+        };
+
+        Self {
+            exec: Exec::new(),
+            search_paths,
+            column_unit: cli.global.column_unit,
+            backup: cli.global.backup,
+            paramstyle: opts.paramstyle.or(config.paramstyle),
+        }
+    }
+
+    fn run(&mut self) {
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (mut code, path) in iter_valid_files(&search_paths) {
+            let path_str = path.as_path().to_str().unwrap();
+            let rewrite = self.rewrite_file(&code);
+            for (offset, reason) in &rewrite.skipped {
+                let (line, col) = line_col(&code, *offset, self.column_unit);
+                print(path_str, line, Some(col), &format!("left alone: {reason}"));
+            }
+
+            if rewrite.edits.is_empty() {
+                continue;
+            }
+            apply_edits(&mut code, rewrite.edits);
+            if write_code(path, &code, self.backup.as_deref()).is_err() {
+                eprintln!("Failed to write to path: {}", path.display());
+            }
+            println!(
+                "{} queries parameterized in {}",
+                rewrite.queries,
+                path.display()
+            );
+        }
+    }
+}
+
+impl Parameterize {
+    fn rewrite_file(&mut self, code: &str) -> Rewrite {
+        let tree = parser_tree(&mut new_parser(), code);
+        let mut rewrite = Rewrite::default();
+        for call in self.exec.calls(code) {
+            if let Err((offset, reason)) =
+                self.rewrite_call(code, tree.root_node(), &call, &mut rewrite)
+            {
+                rewrite.skipped.push((offset, reason));
+            }
+        }
+        rewrite
+    }
+
+    /// Add the edits binding the values spliced into the SQL of `call` as parameters. Fields that
+    /// aren't values are reported, the call is left alone when they can't be kept.
+    fn rewrite_call(
+        &self,
+        code: &str,
+        root: Node<'_>,
+        call: &ExecCall,
+        rewrite: &mut Rewrite,
+    ) -> Result<(), (usize, String)> {
+        let arg_range = call.sql_arg.byte_range.clone();
+        let skip = |reason: &str| Err((arg_range.start, reason.to_string()));
+        let Some(arg) = root.descendant_for_byte_range(arg_range.start, arg_range.end) else {
+            return Ok(());
+        };
+        let (form, string, fields) = match spliced_fields(arg, code) {
+            Ok(Some(spliced)) => spliced,
+            Ok(None) => return Ok(()),
+            Err(reason) => return skip(reason),
+        };
+        if is_executemany(root, call, code) {
+            return skip("values spliced into `executemany` SQL differ per call");
+        }
+
+        let block = SqlBlock {
+            string_start: string.child(0).unwrap().into(),
+            string_end: string.child(string.child_count() - 1).unwrap().into(),
+        };
+        let Some((prefix, delimiter)) = block.opening(code) else {
+            return skip("the string prefix isn't valid Python");
+        };
+        if prefix.bytes {
+            return skip("bytes SQL isn't supported");
+        }
+        let offset = block.inner_text_range().start;
+        let body = block.inner_text(code);
+
+        // each field's slot in the body, or why it's left alone
+        let mut values = Vec::new();
+        let mut kept = 0;
+        for field in &fields {
+            let text = &code[field.range.clone()];
+            let slot = value_slot(body, field.range.start - offset..field.range.end - offset);
+            let reason = match (&field.expr, slot) {
+                (None, _) => "its conversion or format spec can't be bound",
+                (Some(_), Slot::Identifier) => "it looks like a table or column name",
+                (Some(_), Slot::Literal) => "it's part of a SQL string or comment",
+                (Some(expr), Slot::Value(range)) => {
+                    values.push((range, expr.clone(), field));
+                    continue;
+                }
+            };
+            rewrite
+                .skipped
+                .push((field.range.start, format!("`{text}` since {reason}")));
+            kept += 1;
+        }
+        if kept > 0 && form != Form::FString {
+            // `.format()` and `%` apply to the whole string, so nothing can be bound
+            for (_, _, field) in values {
+                let text = &code[field.range.clone()];
+                rewrite.skipped.push((
+                    field.range.start,
+                    format!(
+                        "`{text}` since the string is still formatted for the fields left alone"
+                    ),
+                ));
+            }
+            return Ok(());
+        }
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        // placeholders already bound by the existing parameters
+        let existing = placeholders(body)
+            .into_iter()
+            .filter(|p| {
+                !fields
+                    .iter()
+                    .any(|f| f.range.contains(&(offset + p.range.start)))
+            })
+            .collect::<Vec<_>>();
+        let style = self
+            .paramstyle
+            .or_else(|| existing.first().map(|p| p.style))
+            .unwrap_or(ParamStyle::Format);
+        if existing.iter().any(|p| p.style != style) {
+            return skip("the query already has placeholders of another style");
+        }
+        let named = matches!(style, ParamStyle::Named | ParamStyle::Pyformat);
+        if style == ParamStyle::Numeric && !existing.is_empty() {
+            return skip("numbered placeholders already in the query would need renumbering");
+        }
+
+        let params_node = match &call.params {
+            Some(params) => {
+                let range = params.position.byte_range.clone();
+                let fits = match params.shape {
+                    ParamsShape::Sequence(count) => !named && count == existing.len(),
+                    ParamsShape::Mapping(_) => named,
+                    ParamsShape::Dynamic => false,
+                };
+                if form == Form::Percent || !fits {
+                    return skip("the existing parameters can't be merged with new ones");
+                }
+                root.descendant_for_byte_range(range.start, range.end)
+            }
+            None => None,
+        };
+
+        // name or position of every value, then the placeholders replacing them
+        let mut names = Names::default();
+        if let Some(ParamsShape::Mapping(keys)) = call.params.as_ref().map(|p| &p.shape) {
+            for key in keys {
+                names.taken.insert(key.clone(), None);
+            }
+        }
+        let mut replacements = Vec::new();
+        let mut new_params = Vec::new();
+        for (idx, (range, expr, field)) in values.iter().enumerate() {
+            let name = named.then(|| names.name(field.key.as_deref(), expr));
+            let placeholder = match style {
+                ParamStyle::Qmark => "?".to_string(),
+                ParamStyle::Format => "%s".to_string(),
+                ParamStyle::Numeric => format!(":{}", idx + 1),
+                ParamStyle::Named => format!(":{}", name.as_ref().unwrap()),
+                ParamStyle::Pyformat => format!("%({})s", name.as_ref().unwrap()),
+            };
+            replacements.push((range.clone(), placeholder));
+            new_params.push((range.start, expr.clone(), name));
+        }
+
+        let converts_all = kept == 0;
+        let percent_in = form == Form::Percent || call.params.is_some();
+        let percent_out = matches!(style, ParamStyle::Format | ParamStyle::Pyformat);
+        let mut new_body = String::new();
+        let mut pos = 0;
+        for (range, placeholder) in &replacements {
+            new_body.push_str(&literal_text(
+                &body[pos..range.start],
+                form,
+                converts_all,
+                percent_in,
+                percent_out,
+            ));
+            new_body.push_str(placeholder);
+            pos = range.end;
+        }
+        new_body.push_str(&literal_text(
+            &body[pos..],
+            form,
+            converts_all,
+            percent_in,
+            percent_out,
+        ));
+
+        let mut prefix_text = prefix.text.to_string();
+        if form == Form::FString && converts_all {
+            prefix_text.retain(|ch| !matches!(ch, 'f' | 'F'));
+        }
+        let closing = &code[block.string_end.byte_range.clone()];
+        let literal = format!("{prefix_text}{delimiter}{new_body}{closing}");
+
+        let params = params_text(code, params_node, &existing, new_params);
+        match params_node {
+            Some(node) => {
+                rewrite.edits.push((arg_range, literal));
+                rewrite.edits.push((node.byte_range(), params));
+            }
+            None => rewrite
+                .edits
+                .push((arg_range, format!("{literal}, {params}"))),
+        }
+        rewrite.queries += 1;
+        Ok(())
+    }
+}
+
+/// Parameter names for `named` and `pyformat` placeholders, one per distinct value.
+#[derive(Default)]
+struct Names {
+    /// Names in use and the value they're for, `None` for existing parameters.
+    taken: HashMap<String, Option<String>>,
+}
+
+impl Names {
+    fn name(&mut self, key: Option<&str>, expr: &str) -> String {
+        let base = key.map(str::to_string).unwrap_or_else(|| {
+            let last = expr.rsplit('.').next().unwrap_or(expr).trim();
+            let is_identifier = last.chars().next().is_some_and(|ch| !ch.is_ascii_digit())
+                && last.chars().all(|ch| ch.is_alphanumeric() || ch == '_');
+            if is_identifier { last } else { "param" }.to_string()
+        });
+
+        let mut name = base.clone();
+        let mut count = 1;
+        loop {
+            match self.taken.get(&name) {
+                Some(Some(taken)) if taken == expr => return name,
+                Some(_) => {
+                    count += 1;
+                    name = format!("{base}_{count}");
+                }
+                None => {
+                    self.taken.insert(name.clone(), Some(expr.to_string()));
+                    return name;
+                }
+            }
+        }
+    }
+}
+
+/// The string holding the SQL and the values spliced into it, in order. `Ok(None)` when nothing
+/// is spliced in with an f-string, `.format()` or `%`.
+fn spliced_fields<'t>(arg: Node<'t>, code: &str) -> Result<Option<Spliced<'t>>, &'static str> {
+    let mut tc = arg.walk();
+    match arg.kind() {
+        "string" => {
+            let fields = arg
+                .children(&mut tc)
+                .filter(|child| child.kind() == "interpolation")
+                .map(|interp| {
+                    let mut tc = interp.walk();
+                    let simple = interp.named_child_count() == 1
+                        && !code[interp.byte_range()]
+                            .trim_end_matches('}')
+                            .ends_with('=');
+                    let expr = interp
+                        .children(&mut tc)
+                        .find(|child| child.is_named())
+                        .filter(|_| simple)
+                        .map(|expr| code[expr.byte_range()].to_string());
+                    Field {
+                        range: interp.byte_range(),
+                        expr,
+                        key: None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            Ok((!fields.is_empty()).then_some((Form::FString, arg, fields)))
+        }
+        "call" => {
+            let Some(function) = arg.child_by_field_name("function") else {
+                return Ok(None);
+            };
+            let is_format = function
+                .child_by_field_name("attribute")
+                .is_some_and(|attr| &code[attr.byte_range()] == "format");
+            let Some(string) = function
+                .child_by_field_name("object")
+                .filter(|object| is_format && object.kind() == "string")
+            else {
+                return Ok(None);
+            };
+
+            let mut positional = Vec::new();
+            let mut keywords = HashMap::new();
+            if let Some(args) = arg.child_by_field_name("arguments") {
+                for arg in args.named_children(&mut tc) {
+                    match arg.kind() {
+                        "comment" => {}
+                        "keyword_argument" => {
+                            let (Some(name), Some(value)) = (
+                                arg.child_by_field_name("name"),
+                                arg.child_by_field_name("value"),
+                            ) else {
+                                return Err("a `.format()` argument can't be read");
+                            };
+                            keywords.insert(&code[name.byte_range()], &code[value.byte_range()]);
+                        }
+                        "list_splat" | "dictionary_splat" => {
+                            return Err("`.format()` arguments are unpacked at runtime")
+                        }
+                        _ => positional.push(&code[arg.byte_range()]),
+                    }
+                }
+            }
+
+            let offset = inner_start(string);
+            let body = &code[offset..inner_end(string)];
+            let mut fields = Vec::new();
+            let mut auto_index = 0;
+            for (range, field) in format_fields(body) {
+                let (name, simple) = match field.find(['!', ':']) {
+                    Some(idx) => (&field[..idx], false),
+                    None => (field, true),
+                };
+                let (expr, key) = if name.is_empty() {
+                    auto_index += 1;
+                    (positional.get(auto_index - 1).copied(), None)
+                } else if let Ok(index) = name.parse::<usize>() {
+                    (positional.get(index).copied(), None)
+                } else if name.chars().all(|ch| ch.is_alphanumeric() || ch == '_') {
+                    (keywords.get(name).copied(), Some(name.to_string()))
+                } else {
+                    return Err("`.format()` fields index into their arguments");
+                };
+                let Some(expr) = expr else {
+                    return Err("a `.format()` field has no argument");
+                };
+                fields.push(Field {
+                    range: offset + range.start..offset + range.end,
+                    expr: simple.then(|| expr.to_string()),
+                    key,
+                });
+            }
+            Ok((!fields.is_empty()).then_some((Form::Format, string, fields)))
+        }
+        "binary_operator" => {
+            let operator = arg.child_by_field_name("operator");
+            let (Some(left), Some(right)) = (
+                arg.child_by_field_name("left"),
+                arg.child_by_field_name("right"),
+            ) else {
+                return Ok(None);
+            };
+            let is_percent = operator.is_some_and(|op| &code[op.byte_range()] == "%");
+            if !is_percent || left.kind() != "string" {
+                return Ok(None);
+            }
+
+            let offset = inner_start(left);
+            let markers = percent_fields(&code[offset..inner_end(left)])
+                .ok_or("a `%` conversion other than `%s`, `%d` or `%i` can't be read")?;
+            let values = match right.kind() {
+                "tuple" => right
+                    .named_children(&mut tc)
+                    .filter(|value| value.kind() != "comment")
+                    .map(|value| (None, &code[value.byte_range()]))
+                    .collect::<Vec<_>>(),
+                "dictionary" => {
+                    let mut values = Vec::new();
+                    for pair in right.named_children(&mut tc) {
+                        let key = pair
+                            .child_by_field_name("key")
+                            .filter(|key| key.kind() == "string")
+                            .map(|key| &code[inner_start(key)..inner_end(key)]);
+                        let value = pair.child_by_field_name("value");
+                        match (key, value) {
+                            (Some(key), Some(value)) => {
+                                values.push((Some(key), &code[value.byte_range()]))
+                            }
+                            _ if pair.kind() == "comment" => {}
+                            _ => return Err("the `%` mapping has keys that aren't strings"),
+                        }
+                    }
+                    values
+                }
+                _ => vec![(None, &code[right.byte_range()])],
+            };
+
+            let mut fields = Vec::new();
+            let mut positional = values.iter().filter(|(key, _)| key.is_none());
+            for (range, key, conversion) in markers {
+                let expr = match key {
+                    Some(key) => values
+                        .iter()
+                        .find(|(name, _)| *name == Some(key))
+                        .map(|(_, expr)| *expr),
+                    None => positional.next().map(|(_, expr)| *expr),
+                };
+                let Some(expr) = expr else {
+                    return Err("a `%` field has no value");
+                };
+                // `%d` and `%i` turn the value into an integer first
+                fields.push(Field {
+                    range: offset + range.start..offset + range.end,
+                    expr: (conversion == 's').then(|| expr.to_string()),
+                    key: key.map(str::to_string),
+                });
+            }
+            if positional.next().is_some() {
+                return Err("the `%` values don't match its fields");
+            }
+            Ok((!fields.is_empty()).then_some((Form::Percent, left, fields)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn inner_start(string: Node<'_>) -> usize {
+    string
+        .child(0)
+        .map_or(string.start_byte(), |start| start.end_byte())
+}
+
+fn inner_end(string: Node<'_>) -> usize {
+    let last = string.child_count().saturating_sub(1);
+    string
+        .child(last)
+        .map_or(string.end_byte(), |end| end.start_byte())
+}
+
+/// `{...}` fields of a `.format()` string and the text between their braces.
+fn format_fields(body: &str) -> Vec<(Range<usize>, &str)> {
+    let mut fields = Vec::new();
+    let mut chars = body.char_indices().peekable();
+    while let Some((start, ch)) = chars.next() {
+        match ch {
+            '{' if chars.next_if(|(_, ch)| *ch == '{').is_some() => {}
+            '{' => {
+                let mut depth = 1;
+                for (idx, ch) in chars.by_ref() {
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        fields.push((start..idx + 1, &body[start + 1..idx]));
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+/// A `%` field, its mapping key and conversion.
+type PercentField<'b> = (Range<usize>, Option<&'b str>, char);
+
+/// `%s`, `%d` and `%i` fields of a `%` formatted string. `None` when there's any other
+/// conversion.
+fn percent_fields(body: &str) -> Option<Vec<PercentField<'_>>> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while let Some(idx) = body[pos..].find('%') {
+        let start = pos + idx;
+        let rest = &body[start + 1..];
+        let (key, conversion_at) = match rest.strip_prefix('(') {
+            Some(named) => {
+                let close = named.find(')')?;
+                (Some(&named[..close]), start + close + 3)
+            }
+            None => (None, start + 1),
+        };
+        match body[conversion_at..].chars().next() {
+            Some('%') if key.is_none() => {}
+            Some(conversion @ ('s' | 'd' | 'i')) => {
+                fields.push((start..conversion_at + 1, key, conversion))
+            }
+            _ => return None,
+        }
+        pos = conversion_at + 1;
+    }
+    Some(fields)
+}
+
+/// SQL text between replaced fields, with the escapes that change once values are bound: `{{`
+/// when the string stops being formatted and `%%` when the driver does or doesn't expect it.
+fn literal_text(
+    text: &str,
+    form: Form,
+    converts_all: bool,
+    percent_in: bool,
+    percent_out: bool,
+) -> String {
+    let braces = form != Form::Percent;
+    let mut literal = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' | '}' if braces && chars.peek() == Some(&ch) => {
+                chars.next();
+                literal.push(ch);
+                if !converts_all {
+                    literal.push(ch);
+                }
+            }
+            '{' if braces => {
+                // a field left alone
+                literal.push(ch);
+                let mut depth = 1;
+                while let Some(ch) = chars.next_if(|_| depth > 0) {
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    literal.push(ch);
+                }
+            }
+            '%' if percent_in && chars.peek() == Some(&'%') => {
+                chars.next();
+                literal.push_str(if percent_out { "%%" } else { "%" });
+            }
+            '%' if !percent_in && percent_out => literal.push_str("%%"),
+            _ => literal.push(ch),
+        }
+    }
+    literal
+}
+
+/// The parameters argument with the new values merged in, by name or in placeholder order.
+fn params_text(
+    code: &str,
+    existing_node: Option<Node<'_>>,
+    existing: &[Placeholder],
+    new: Vec<(usize, String, Option<String>)>,
+) -> String {
+    let mut tc = existing_node.map(|node| node.walk());
+    let existing_values = match (existing_node, tc.as_mut()) {
+        (Some(node), Some(tc)) => node
+            .named_children(tc)
+            .filter(|child| child.kind() != "comment")
+            .map(|child| &code[child.byte_range()])
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+
+    if new.iter().any(|(_, _, name)| name.is_some()) {
+        let mut entries = existing_values
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let mut seen = Vec::new();
+        for (_, expr, name) in new {
+            let name = name.unwrap();
+            if !seen.contains(&name) {
+                entries.push(format!("\"{name}\": {expr}"));
+                seen.push(name);
+            }
+        }
+        return format!("{{{}}}", entries.join(", "));
+    }
+
+    // existing values in the order of their placeholders, with the new ones slotted in
+    let mut ordered = existing
+        .iter()
+        .zip(existing_values)
+        .map(|(placeholder, value)| (placeholder.range.start, value.to_string()))
+        .collect::<Vec<_>>();
+    ordered.extend(new.into_iter().map(|(start, expr, _)| (start, expr)));
+    ordered.sort_by_key(|(start, _)| *start);
+    let values = ordered
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect::<Vec<_>>();
+
+    match existing_node.map(|node| node.kind()) {
+        Some("list") => format!("[{}]", values.join(", ")),
+        _ if values.len() == 1 => format!("({},)", values[0]),
+        _ => format!("({})", values.join(", ")),
+    }
+}
+
+fn is_executemany(root: Node<'_>, call: &ExecCall, code: &str) -> bool {
+    let range = call.call.byte_range.clone();
+    root.descendant_for_byte_range(range.start, range.end)
+        .and_then(|call| call.child_by_field_name("function"))
+        .and_then(|func| func.child_by_field_name("attribute"))
+        .is_some_and(|attr| &code[attr.byte_range()] == "executemany")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parameterize(code: &str, paramstyle: Option<ParamStyle>) -> (String, Vec<String>) {
+        let mut program = Parameterize {
+            exec: Exec::new(),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            backup: None,
+            paramstyle,
+        };
+        let rewrite = program.rewrite_file(code);
+        let skipped = rewrite
+            .skipped
+            .iter()
+            .map(|(offset, reason)| format!("{}: {reason}", line_number(code, *offset)))
+            .collect();
+        let mut code = code.to_string();
+        apply_edits(&mut code, rewrite.edits);
+        (code, skipped)
+    }
+
+    #[test]
+    fn format_style() {
+        let code = r#"
+crs.execute(f"SELECT * FROM t WHERE id = {uid}")
+crs.execute(f"SELECT * FROM {table} WHERE name = '{name}' AND x LIKE 'a%'")
+crs.execute("SELECT * FROM t WHERE a = {} AND b = {b}".format(a, b=user.b))
+crs.execute("SELECT * FROM t WHERE a = %s AND b = '%s' AND c LIKE 'x%%'" % (a, b))
+crs.execute(f"SELECT * FROM t WHERE a = %s AND b = {b} AND c = %s", [a, c])
+crs.execute("SELECT {} FROM t WHERE a = {}".format(col, a))
+crs.execute(f"SELECT * FROM t WHERE a = {a:>5}")
+crs.execute("SELECT * FROM t WHERE a = %d AND b = %s" % (a, b))
+"#;
+        let expect = r#"
+crs.execute("SELECT * FROM t WHERE id = %s", (uid,))
+crs.execute(f"SELECT * FROM {table} WHERE name = %s AND x LIKE 'a%%'", (name,))
+crs.execute("SELECT * FROM t WHERE a = %s AND b = %s", (a, user.b))
+crs.execute("SELECT * FROM t WHERE a = %s AND b = %s AND c LIKE 'x%%'", (a, b))
+crs.execute("SELECT * FROM t WHERE a = %s AND b = %s AND c = %s", [a, b, c])
+crs.execute("SELECT {} FROM t WHERE a = {}".format(col, a))
+crs.execute(f"SELECT * FROM t WHERE a = {a:>5}")
+crs.execute("SELECT * FROM t WHERE a = %d AND b = %s" % (a, b))
+"#;
+        let (code, skipped) = parameterize(code, None);
+        assert_eq!(code, expect);
+        assert_eq!(
+            skipped,
+            vec![
+                "3: `{table}` since it looks like a table or column name",
+                "7: `{}` since it looks like a table or column name",
+                "7: `{}` since the string is still formatted for the fields left alone",
+                "8: `{a:>5}` since its conversion or format spec can't be bound",
+                "9: `%d` since its conversion or format spec can't be bound",
+                "9: `%s` since the string is still formatted for the fields left alone",
+            ]
+        );
+    }
+
+    #[test]
+    fn named_styles() {
+        let code = r#"
+crs.execute(f"SELECT * FROM t WHERE a = {user.id} AND b = {id} AND c = {user.id}")
+crs.execute("SELECT * FROM t WHERE a = %(a)s AND b = %(b)s" % {"a": x, "b": y})
+crs.execute(f"SELECT * FROM t WHERE a = :a AND b = {b}", {"a": a})
+"#;
+        let expect = r#"
+crs.execute("SELECT * FROM t WHERE a = :id AND b = :id_2 AND c = :id", {"id": user.id, "id_2": id})
+crs.execute("SELECT * FROM t WHERE a = :a AND b = :b", {"a": x, "b": y})
+crs.execute("SELECT * FROM t WHERE a = :a AND b = :b", {"a": a, "b": b})
+"#;
+        let (code, skipped) = parameterize(code, Some(ParamStyle::Named));
+        assert_eq!(code, expect);
+        assert!(skipped.is_empty());
+    }
+}
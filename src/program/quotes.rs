@@ -1,35 +1,86 @@
+use std::ops::Range;
 use std::path::PathBuf;
 
+use tree_sitter::Node;
+
 use super::Program;
-use crate::cli::Cli;
-use crate::treesitter::{ts_query_factory, SqlBlock, TreesitterQuery};
+use crate::cli::{Cli, QuoteStyle, QuotesOptions};
+use crate::error_exit;
+use crate::sql::lexer::{tokenize, TokenKind};
+use crate::treesitter::{
+    new_parser, parser_tree, plain_body, reencode, string_value, string_value_at,
+};
+use crate::treesitter::{ts_query_factory, Exec, SqlBlock, StringPrefix, TreesitterQuery};
 use crate::utils::*;
 
-const TRIPLE_QUOTES: &str = "\"\"\"";
-
 pub(crate) struct Quotes {
     treesitter: Box<dyn TreesitterQuery>,
     search_paths: Vec<PathBuf>,
+    backup: Option<String>,
+    style: QuoteStyle,
+    only_multiline: bool,
+    reflow: bool,
+    merge: bool,
+    exec: Exec,
 }
 
 impl Program for Quotes {
     fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: QuotesOptions = cli.command.into();
+        if (opts.reflow || opts.merge) && opts.style.delimiter().len() == 1 {
+            error_exit!("--reflow and --merge need a triple-quoted --style");
+        }
+
         Self {
-            treesitter: ts_query_factory(&cli),
-            search_paths: cli.search_paths(),
+            treesitter,
+            search_paths,
+            backup: cli.global.backup,
+            style: opts.style,
+            only_multiline: opts.only_multiline,
+            reflow: opts.reflow,
+            merge: opts.merge,
+            exec: Exec::new(),
         }
     }
 
     fn run(&mut self) {
         for (mut code, path) in iter_valid_files(&self.search_paths) {
             let mut change_count = 0;
+            let calls = if self.merge {
+                self.exec.calls(&code)
+            } else {
+                Vec::new()
+            };
+            for call in calls.iter().rev() {
+                match merge_fragments(&mut code, call.sql_arg.byte_range.clone(), self.style) {
+                    Ok(true) => change_count += 1,
+                    Ok(false) => {}
+                    Err(reason) => eprintln!(
+                        "{}:{}: not merged, {reason}",
+                        path.display(),
+                        call.sql_arg.point.row + 1
+                    ),
+                }
+            }
+
             for block in self.treesitter.sql_blocks(&code).iter().rev() {
-                if replace_quotes(&mut code, block) {
-                    change_count += 1;
+                if self.only_multiline && !block.inner_text(&code).contains('\n') {
+                    continue;
+                }
+                match replace_quotes(&mut code, block, self.style, self.reflow) {
+                    Ok(true) => change_count += 1,
+                    Ok(false) => {}
+                    Err(reason) => eprintln!(
+                        "{}:{}: skipped, {reason}",
+                        path.display(),
+                        block.start_line_num()
+                    ),
                 }
             }
 
-            if write_file(path, code.as_bytes()).is_err() {
+            if change_count > 0 && write_code(path, &code, self.backup.as_deref()).is_err() {
                 eprintln!("Failed to write to path: {}", path.display());
             }
             println!("{change_count} changes made to {}", path.display());
@@ -37,29 +88,216 @@ impl Program for Quotes {
     }
 }
 
-fn replace_quotes(code: &mut String, block: &SqlBlock) -> bool {
-    let mut start = block.string_start.byte_range.clone();
-    let end = block.string_end.byte_range.clone();
+/// Convert the quotes of `block` to `style`, keeping any string prefix, and `reflow` the SQL.
+/// Strings spanning several lines are left alone unless converting to triple quotes. The body is
+/// re-encoded for the new quotes, and the edit is undone unless the file still parses to a string
+/// with the same value.
+fn replace_quotes(
+    code: &mut String,
+    block: &SqlBlock,
+    style: QuoteStyle,
+    reflow: bool,
+) -> Result<bool, &'static str> {
+    let (prefix, delimiter) = block
+        .opening(code)
+        .ok_or("the string prefix isn't valid Python")?;
+
+    let target = style.delimiter();
+    let mut body = block.inner_text(code).to_string();
+    if reflow {
+        body = reflowed(code, block).ok_or("the SQL has literals spanning lines")?;
+    }
+    if delimiter == target && body == block.inner_text(code) {
+        return Ok(false);
+    }
+    if target.len() == 1 && body.contains('\n') {
+        return Ok(false);
+    }
+    let encoded = reencode(&body, prefix, target).ok_or(if prefix.raw {
+        "escaping quotes would change the value of a raw string"
+    } else {
+        "quotes inside f-string replacement fields can't be escaped"
+    })?;
+
+    let value = string_value(&body, prefix);
+    let literal = format!("{}{target}{encoded}{target}", prefix.text);
+    let range = block.string_start.byte_range.start..block.string_end.byte_range.end;
+    let original = code[range.clone()].to_string();
+    code.replace_range(range.clone(), &literal);
+
+    let new_range = range.start..range.start + literal.len();
+    if string_value_at(code, new_range.clone()).as_ref() != Some(&value) {
+        code.replace_range(new_range, &original);
+        return Err("the converted string doesn't parse to the same value");
+    }
+    Ok(true)
+}
 
-    if is_f_string(&code[start.start..start.end]) {
-        start = start.start + 1..start.end;
+/// The body of `block` with the SQL on its own lines, indented one level deeper than the line the
+/// string starts on and with the closing quotes on a line of their own. `None` when that would
+/// change SQL strings or quoted identifiers spanning lines.
+fn reflowed(code: &str, block: &SqlBlock) -> Option<String> {
+    let body = block.inner_text(code);
+    let significant = tokenize(body).iter().any(|tok| {
+        matches!(tok.kind, TokenKind::String | TokenKind::QuotedIdent) && tok.text.contains('\n')
+    });
+    if significant {
+        return None;
     }
 
-    if &code[start.start..start.end] == TRIPLE_QUOTES {
-        return false;
+    let start = &block.string_start;
+    let line = &code[start.byte_range.start - start.point.column..];
+    let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+
+    // SQL starting on the same line as the quotes has lost its indentation already
+    let (first, rest) = match body.split_once('\n') {
+        Some((first, rest)) if !first.trim().is_empty() => (Some(first.trim()), rest),
+        Some((_, rest)) => (None, rest),
+        None => (Some(body.trim()), ""),
+    };
+    let rest = textwrap::dedent(rest);
+    let lines = first
+        .into_iter()
+        .chain(rest.lines())
+        .map(str::trim_end)
+        .skip_while(|line| line.is_empty())
+        .collect::<Vec<_>>();
+    let last = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |idx| idx + 1);
+
+    let mut reflowed = String::from("\n");
+    for line in &lines[..last] {
+        if !line.is_empty() {
+            reflowed.push_str(indent);
+            reflowed.push_str("    ");
+            reflowed.push_str(line);
+        }
+        reflowed.push('\n');
+    }
+    reflowed.push_str(indent);
+    Some(reflowed)
+}
+
+/// Replace the SQL argument spanning `range` with a single string, when it's string literals
+/// joined by `+` or written next to each other. The result is an f-string if any of them was one,
+/// and the edit is undone unless the file parses to a string with the value of the concatenation.
+fn merge_fragments(
+    code: &mut String,
+    range: Range<usize>,
+    style: QuoteStyle,
+) -> Result<bool, &'static str> {
+    let tree = parser_tree(&mut new_parser(), code);
+    let Some(arg) = tree
+        .root_node()
+        .descendant_for_byte_range(range.start, range.end)
+    else {
+        return Ok(false);
+    };
+    if arg.kind() == "string" || !joins_strings(arg) {
+        return Ok(false);
+    }
+    let mut fragments = Vec::new();
+    string_fragments(arg, code, &mut fragments)
+        .ok_or("only string literals joined by `+` can be merged")?;
+    if fragments.len() < 2 {
+        return Ok(false);
     }
 
-    code.replace_range(end, TRIPLE_QUOTES);
-    code.replace_range(start, TRIPLE_QUOTES);
-    true
+    let prefixes = fragments
+        .iter()
+        .map(|block| block.opening(code).map(|(prefix, _)| prefix))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("a string prefix isn't valid Python")?;
+    let bytes = prefixes.iter().filter(|prefix| prefix.bytes).count();
+    if bytes != 0 && bytes != prefixes.len() {
+        return Err("bytes and str literals can't be joined");
+    }
+    let format = prefixes.iter().any(|prefix| prefix.format);
+    let prefix = StringPrefix {
+        text: if bytes > 0 {
+            "b"
+        } else if format {
+            "f"
+        } else {
+            ""
+        },
+        raw: false,
+        bytes: bytes > 0,
+        format,
+    };
+
+    let mut body = String::new();
+    let mut value = String::new();
+    for (block, fragment_prefix) in fragments.iter().zip(prefixes) {
+        let fragment = block.inner_text(code);
+        body.push_str(&plain_body(fragment, fragment_prefix, format));
+        let fragment_value = string_value(fragment, fragment_prefix);
+        if format && !fragment_prefix.format {
+            value.push_str(&fragment_value.replace('{', "{{").replace('}', "}}"));
+        } else {
+            value.push_str(&fragment_value);
+        }
+    }
+
+    let target = style.delimiter();
+    let encoded = reencode(&body, prefix, target)
+        .ok_or("quotes inside f-string replacement fields can't be escaped")?;
+    let literal = format!("{}{target}{encoded}{target}", prefix.text);
+    let original = code[range.clone()].to_string();
+    code.replace_range(range.clone(), &literal);
+
+    let new_range = range.start..range.start + literal.len();
+    if string_value_at(code, new_range.clone()).as_ref() != Some(&value) {
+        code.replace_range(new_range, &original);
+        return Err("the merged string doesn't parse to the same value");
+    }
+    Ok(true)
 }
 
-fn is_f_string(code: &str) -> bool {
-    if let Some(first_letter) = code.chars().next() {
-        first_letter == 'f'
-    } else {
-        false
+/// Whether `node` is strings written next to each other or a `+` with a string on either side,
+/// so calls building their SQL any other way aren't reported.
+fn joins_strings(node: Node<'_>) -> bool {
+    match node.kind() {
+        "string" | "concatenated_string" => true,
+        "parenthesized_expression" => node.named_child(0).is_some_and(joins_strings),
+        "binary_operator" => {
+            node.child_by_field_name("operator")
+                .is_some_and(|op| op.kind() == "+")
+                && ["left", "right"]
+                    .iter()
+                    .any(|field| node.child_by_field_name(field).is_some_and(joins_strings))
+        }
+        _ => false,
+    }
+}
+
+/// The string literals making up `node`, in order. `None` when anything but strings, `+` and
+/// parentheses is involved, including comments that merging would drop.
+fn string_fragments(node: Node<'_>, code: &str, fragments: &mut Vec<SqlBlock>) -> Option<()> {
+    let mut tc = node.walk();
+    match node.kind() {
+        "string" => fragments.push(SqlBlock {
+            string_start: node.child(0)?.into(),
+            string_end: node.child(node.child_count() - 1)?.into(),
+        }),
+        "concatenated_string" | "parenthesized_expression" => {
+            for child in node.named_children(&mut tc) {
+                string_fragments(child, code, fragments)?;
+            }
+        }
+        "binary_operator" => {
+            let operator = node.child_by_field_name("operator")?;
+            if &code[operator.byte_range()] != "+" {
+                return None;
+            }
+            string_fragments(node.child_by_field_name("left")?, code, fragments)?;
+            string_fragments(node.child_by_field_name("right")?, code, fragments)?;
+        }
+        _ => return None,
     }
+    Some(())
 }
 
 #[cfg(test)]
@@ -81,7 +319,7 @@ mod test {
         let blocks = ts.sql_blocks(&code);
         let block = &blocks[0];
         assert_eq!(blocks.len(), 1);
-        replace_quotes(&mut code, block);
+        replace_quotes(&mut code, block, QuoteStyle::TripleDouble, false).unwrap();
 
         assert_eq!(code, expect);
     }
@@ -95,7 +333,7 @@ mod test {
         let blocks = ts.sql_blocks(&code);
         let block = &blocks[0];
         assert_eq!(blocks.len(), 1);
-        replace_quotes(&mut code, block);
+        replace_quotes(&mut code, block, QuoteStyle::TripleDouble, false).unwrap();
 
         assert_eq!(code, expect);
     }
@@ -109,7 +347,7 @@ mod test {
         let blocks = ts.sql_blocks(&code);
         let block = &blocks[0];
         assert_eq!(blocks.len(), 1);
-        replace_quotes(&mut code, block);
+        replace_quotes(&mut code, block, QuoteStyle::TripleDouble, false).unwrap();
 
         assert_eq!(code, expect);
     }
@@ -123,7 +361,7 @@ mod test {
         let blocks = ts.sql_blocks(&code);
         let block = &blocks[0];
         assert_eq!(blocks.len(), 1);
-        replace_quotes(&mut code, block);
+        replace_quotes(&mut code, block, QuoteStyle::TripleDouble, false).unwrap();
 
         assert_eq!(code, expect);
     }
@@ -146,7 +384,7 @@ crs.execute(f"""
         let blocks = ts.sql_blocks(&code);
         let block = &blocks[0];
         assert_eq!(blocks.len(), 1);
-        replace_quotes(&mut code, block);
+        replace_quotes(&mut code, block, QuoteStyle::TripleDouble, false).unwrap();
 
         assert_eq!(code, expect);
     }
@@ -180,9 +418,204 @@ crs.execute(f"""
         let mut ts = exec_get_ts(QUERY);
         let blocks = ts.sql_blocks(&code);
         for block in blocks.iter().rev() {
-            replace_quotes(&mut code, block);
+            replace_quotes(&mut code, block, QuoteStyle::TripleDouble, false).unwrap();
         }
 
         assert_eq!(code, expect);
     }
+
+    fn convert(code: &str, style: QuoteStyle) -> (String, Vec<Result<bool, &'static str>>) {
+        let mut code = code.to_string();
+        let blocks = Exec::new().sql_blocks(&code);
+        let results = blocks
+            .iter()
+            .rev()
+            .map(|block| replace_quotes(&mut code, block, style, false))
+            .collect();
+        (code, results)
+    }
+
+    #[test]
+    fn collapse_triple_quotes() {
+        let code = r#"
+crs.execute("""SELECT 'a' FROM foo""")
+crs.execute(f"""SELECT {x} FROM foo""")
+crs.execute("""
+    SELECT 1 FROM foo
+""")"#;
+        let expect = r#"
+crs.execute('SELECT \'a\' FROM foo')
+crs.execute(f'SELECT {x} FROM foo')
+crs.execute("""
+    SELECT 1 FROM foo
+""")"#;
+        let (code, results) = convert(code, QuoteStyle::Single);
+        assert_eq!(code, expect);
+        assert_eq!(results, vec![Ok(false), Ok(true), Ok(true)]);
+    }
+
+    #[test]
+    fn escapes_target_quotes() {
+        let (code, _) = convert(
+            r#"crs.execute('SELECT "a""""b" FROM foo')"#,
+            QuoteStyle::TripleDouble,
+        );
+        assert_eq!(code, r#"crs.execute("""SELECT "a""\""b" FROM foo""")"#);
+
+        let (code, _) = convert(
+            r#"crs.execute('SELECT 1 AS "a"')"#,
+            QuoteStyle::TripleDouble,
+        );
+        assert_eq!(code, r#"crs.execute("""SELECT 1 AS "a\"""")"#);
+
+        let (code, _) = convert(
+            r#"crs.execute('SELECT "a", \'b\' FROM foo')"#,
+            QuoteStyle::Double,
+        );
+        assert_eq!(code, r#"crs.execute("SELECT \"a\", 'b' FROM foo")"#);
+
+        let (code, results) = convert(r#"crs.execute(r'SELECT "a" FROM foo')"#, QuoteStyle::Double);
+        assert_eq!(code, r#"crs.execute(r'SELECT "a" FROM foo')"#);
+        assert!(results[0].is_err());
+    }
+
+    #[test]
+    fn string_prefixes() {
+        let code = r#"
+crs.execute(rf'SELECT {x} FROM foo')
+crs.execute(Rb"SELECT 1 FROM foo")
+crs.execute(u'SELECT 1 FROM foo')
+crs.execute(F'SELECT {x} FROM foo')
+crs.execute(fr"SELECT '\d' FROM foo")"#;
+        let expect = r#"
+crs.execute(rf"""SELECT {x} FROM foo""")
+crs.execute(Rb"""SELECT 1 FROM foo""")
+crs.execute(u"""SELECT 1 FROM foo""")
+crs.execute(F"""SELECT {x} FROM foo""")
+crs.execute(fr"""SELECT '\d' FROM foo""")"#;
+        let (code, results) = convert(code, QuoteStyle::TripleDouble);
+        assert_eq!(code, expect);
+        assert!(results.iter().all(|result| result == &Ok(true)));
+
+        let (code, results) = convert(r#"crs.execute(rb"SELECT 'a'")"#, QuoteStyle::Single);
+        assert_eq!(code, r#"crs.execute(rb"SELECT 'a'")"#);
+        assert_eq!(
+            results,
+            vec![Err(
+                "escaping quotes would change the value of a raw string"
+            )]
+        );
+    }
+
+    #[test]
+    fn reencodes_escapes() {
+        let code = r#"
+crs.execute('It\'s')
+crs.execute("say \"hi\"")
+crs.execute(f'SELECT {x} AS \'a\'')
+crs.execute(b'\x00\'')"#;
+        let expect = r#"
+crs.execute("""It's""")
+crs.execute("""say "hi\"""")
+crs.execute(f"""SELECT {x} AS 'a'""")
+crs.execute(b"""\x00'""")"#;
+        let (code, results) = convert(code, QuoteStyle::TripleDouble);
+        assert_eq!(code, expect);
+        assert!(results.iter().all(|result| result == &Ok(true)));
+    }
+
+    #[test]
+    fn reflow() {
+        let code = r#"
+def get(crs):
+    crs.execute('SELECT a FROM foo WHERE b = ?', (b,))
+    crs.execute("""SELECT a
+                   FROM foo
+
+                   WHERE b = 1   """)
+    if x:
+        crs.execute(f"""
+SELECT {col}
+  FROM foo""")
+    crs.execute("""SELECT 'a
+b' FROM foo""")"#;
+        let expect = r#"
+def get(crs):
+    crs.execute("""
+        SELECT a FROM foo WHERE b = ?
+    """, (b,))
+    crs.execute("""
+        SELECT a
+        FROM foo
+
+        WHERE b = 1
+    """)
+    if x:
+        crs.execute(f"""
+            SELECT {col}
+              FROM foo
+        """)
+    crs.execute("""SELECT 'a
+b' FROM foo""")"#;
+        let mut code = code.to_string();
+        let blocks = Exec::new().sql_blocks(&code);
+        let results = blocks
+            .iter()
+            .rev()
+            .map(|block| replace_quotes(&mut code, block, QuoteStyle::TripleDouble, true))
+            .collect::<Vec<_>>();
+        assert_eq!(code, expect);
+        assert_eq!(results[0], Err("the SQL has literals spanning lines"));
+    }
+
+    #[test]
+    fn merge() {
+        let code = r#"
+crs.execute('SELECT a ' "FROM b " + 'WHERE c = \'x\'')
+crs.execute("SELECT {a} "
+            f"FROM {table} "
+            + (r"WHERE c ~ '\d'"))
+crs.execute('SELECT a ' + str(b))
+crs.execute(b'SELECT ' 'a')
+crs.execute('\1' '2')
+crs.execute("SELECT {} ".format(a) + b)
+crs.execute("SELECT %s" % a)
+crs.execute(query + suffix)"#;
+        let expect = r#"
+crs.execute("""SELECT a FROM b WHERE c = 'x'""")
+crs.execute(f"""SELECT {{a}} FROM {table} WHERE c ~ '\\d'""")
+crs.execute('SELECT a ' + str(b))
+crs.execute(b'SELECT ' 'a')
+crs.execute('\1' '2')
+crs.execute("SELECT {} ".format(a) + b)
+crs.execute("SELECT %s" % a)
+crs.execute(query + suffix)"#;
+        let mut code = code.to_string();
+        let calls = Exec::new().calls(&code);
+        let results = calls
+            .iter()
+            .rev()
+            .map(|call| {
+                merge_fragments(
+                    &mut code,
+                    call.sql_arg.byte_range.clone(),
+                    QuoteStyle::TripleDouble,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(code, expect);
+        assert_eq!(
+            results,
+            vec![
+                Ok(false),
+                Ok(false),
+                Ok(false),
+                Err("the merged string doesn't parse to the same value"),
+                Err("bytes and str literals can't be joined"),
+                Err("only string literals joined by `+` can be merged"),
+                Ok(true),
+                Ok(true),
+            ]
+        );
+    }
 }
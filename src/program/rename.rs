@@ -0,0 +1,287 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use console::style;
+
+use super::rg::confirm::{Confirm, ConfirmAns};
+use super::rg::utils::{apply_edits, block_lines, CodeDiff, MatchRange};
+use super::Program;
+use crate::cli::{Cli, ColumnUnit, RenameOptions};
+use crate::error_exit;
+use crate::sql::{self, rename_edits};
+use crate::treesitter::{reencode, string_value, string_value_at, ts_query_factory};
+use crate::treesitter::{SqlBlock, TreesitterQuery};
+use crate::utils::*;
+
+pub(crate) struct Rename {
+    treesitter: Box<dyn TreesitterQuery>,
+    search_paths: Vec<PathBuf>,
+    column_unit: ColumnUnit,
+    backup: Option<String>,
+    renames: Vec<sql::Rename>,
+    confirm: Option<Confirm>,
+}
+
+impl Program for Rename {
+    fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: RenameOptions = cli.command.into();
+
+        let tables = opts.table.iter().map(|arg| {
+            sql::Rename::table(arg)
+                .unwrap_or_else(|err| error_exit!("Invalid --table {}: {}", arg, err))
+        });
+        let columns = opts.column.iter().map(|arg| {
+            sql::Rename::column(arg)
+                .unwrap_or_else(|err| error_exit!("Invalid --column {}: {}", arg, err))
+        });
+
+        Self {
+            treesitter,
+            search_paths,
+            column_unit: cli.global.column_unit,
+            backup: cli.global.backup,
+            renames: tables.chain(columns).collect(),
+            confirm: opts.confirm.then(|| Confirm::new(opts.context)),
+        }
+    }
+
+    fn run(&mut self) {
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (mut code, path) in iter_valid_files(&search_paths) {
+            let path = path.as_path().to_str().unwrap();
+            let edits = self.file_edits(&code, path);
+            if !edits.is_empty() {
+                let change_count = edits.len();
+                apply_edits(&mut code, edits);
+                if write_code(&path, &code, self.backup.as_deref()).is_err() {
+                    eprintln!("Failed to write to path: {}", path);
+                }
+                println!("{change_count} changes made to {path}");
+            }
+
+            if self.confirm.as_ref().is_some_and(|confirm| confirm.quit()) {
+                break;
+            }
+        }
+
+        if let Some(confirm) = &self.confirm {
+            confirm.finish();
+        }
+    }
+}
+
+impl Rename {
+    /// Renames to make in `code` as absolute byte ranges, asking for each one in `--confirm`
+    /// mode.
+    fn file_edits(&mut self, code: &str, path: &str) -> Vec<(Range<usize>, String)> {
+        let lines = block_lines(code);
+        let mut accepted = Vec::new();
+
+        for block in self.treesitter.sql_blocks(code) {
+            let sql = block.inner_text(code);
+            let offset = block.inner_text_range().start;
+            let mut display_sql = sql.to_string();
+            let mut shift = 0;
+
+            let renamed = rename_edits(sql, &self.renames);
+            for (range, reason) in renamed.skipped {
+                let (line, col) = line_col(code, range.start + offset, self.column_unit);
+                eprintln!("{path}:{line}:{col}: not renamed, {reason}");
+            }
+
+            for (range, new) in renamed.edits {
+                let new = match encode_edit(code, &block, range.clone(), &new) {
+                    Ok(encoded) => encoded,
+                    Err(reason) => {
+                        let (line, col) = line_col(code, range.start + offset, self.column_unit);
+                        eprintln!("{path}:{line}:{col}: not renamed, {reason}");
+                        continue;
+                    }
+                };
+                let abs_range = range.start + offset..range.end + offset;
+                let Some(confirm) = self.confirm.as_mut().filter(|c| !c.accept_all()) else {
+                    accepted.push((abs_range, new));
+                    continue;
+                };
+
+                let rng = MatchRange {
+                    block_match_range: range.clone(),
+                    ..MatchRange::from_abs_range(abs_range.clone(), &lines, code, self.column_unit)
+                };
+                let display_rng = rng.shifted_ranged(shift).block_match_range();
+                let description = format!(
+                    "{}: {}\n{}: {}",
+                    style("Rename").red(),
+                    &sql[range.clone()],
+                    style("To").green(),
+                    new
+                );
+                let ans = confirm
+                    .ask(
+                        path,
+                        &rng,
+                        &description,
+                        CodeDiff::new_block(sql, &rng),
+                        CodeDiff::new_raw(
+                            &display_sql[..display_rng.start],
+                            &new,
+                            &display_sql[display_rng.end..],
+                        ),
+                    )
+                    .expect("failed to print confirmation message or get response");
+
+                match ans {
+                    ConfirmAns::Yes | ConfirmAns::All => {
+                        display_sql.replace_range(display_rng, &new);
+                        shift += new.len() as isize - range.len() as isize;
+                        accepted.push((abs_range, new));
+                    }
+                    ConfirmAns::No => {}
+                    ConfirmAns::Quit => return accepted,
+                }
+            }
+        }
+        accepted
+    }
+}
+
+/// `new`, the SQL replacing `range` of the text between the quotes of `block`, written so the
+/// string holding it keeps its meaning. Anything but a plain identifier is checked by parsing the
+/// file with the edit.
+fn encode_edit(
+    code: &str,
+    block: &SqlBlock,
+    range: Range<usize>,
+    new: &str,
+) -> Result<String, &'static str> {
+    if !new.contains(['\'', '"', '\\', '{', '}']) {
+        return Ok(new.to_string());
+    }
+    let (prefix, delimiter) = block
+        .opening(code)
+        .ok_or("the string prefix isn't valid Python")?;
+    let body = block.inner_text(code);
+    if prefix.format && in_replacement_field(body, range.start) {
+        return Err("it's inside an f-string replacement field");
+    }
+
+    let inserted = if prefix.format {
+        new.replace('{', "{{").replace('}', "}}")
+    } else {
+        new.to_string()
+    };
+    let encoded = reencode(&inserted, prefix, delimiter).ok_or(if prefix.raw {
+        "escaping quotes would change the value of a raw string"
+    } else {
+        "quotes inside f-string replacement fields can't be escaped"
+    })?;
+
+    let value = format!(
+        "{}{inserted}{}",
+        string_value(&body[..range.start], prefix),
+        string_value(&body[range.end..], prefix)
+    );
+    let offset = block.inner_text_range().start;
+    let mut edited = code.to_string();
+    edited.replace_range(range.start + offset..range.end + offset, &encoded);
+    let literal = block.string_start.byte_range.start
+        ..block.string_end.byte_range.end + encoded.len() - range.len();
+    if string_value_at(&edited, literal).as_ref() != Some(&value) {
+        return Err("the renamed string doesn't parse to the same value");
+    }
+    Ok(encoded)
+}
+
+/// Whether `pos` in the text between the quotes of an f-string is inside a replacement field.
+fn in_replacement_field(body: &str, pos: usize) -> bool {
+    let mut depth = 0;
+    let mut chars = body[..pos].chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' | '}' if depth == 0 && chars.next_if_eq(&ch).is_some() => {}
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treesitter::Exec;
+
+    #[test]
+    fn renames() {
+        let code = r#"
+crs.execute("SELECT u.name, users.email FROM users u WHERE u.name = 'users'")
+crs.execute(f"""
+    -- copy users over
+    INSERT INTO users_archive (name) SELECT name FROM {table} WHERE name = ?
+""", (name,))
+crs.execute("UPDATE [users] SET name = ? WHERE id = ?", (name, id))
+"#;
+        let mut rename = Rename {
+            treesitter: Box::new(Exec::new()),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            backup: None,
+            renames: vec![
+                sql::Rename::table("users=accounts").unwrap(),
+                sql::Rename::column("users.name=full_name").unwrap(),
+            ],
+            confirm: None,
+        };
+
+        let mut renamed = code.to_string();
+        apply_edits(&mut renamed, rename.file_edits(code, "foo.py"));
+        assert_eq!(
+            renamed,
+            r#"
+crs.execute("SELECT u.full_name, accounts.email FROM accounts u WHERE u.full_name = 'users'")
+crs.execute(f"""
+    -- copy users over
+    INSERT INTO users_archive (name) SELECT name FROM {table} WHERE name = ?
+""", (name,))
+crs.execute("UPDATE [accounts] SET full_name = ? WHERE id = ?", (name, id))
+"#
+        );
+    }
+
+    #[test]
+    fn quoted_names_are_escaped() {
+        let code = r#"
+crs.execute("SELECT id FROM users")
+crs.execute('SELECT id FROM users')
+crs.execute("""SELECT id FROM users""")
+crs.execute(f"SELECT {col} FROM users")
+crs.execute(r"SELECT id FROM users")
+"#;
+        let mut rename = Rename {
+            treesitter: Box::new(Exec::new()),
+            search_paths: Vec::new(),
+            column_unit: ColumnUnit::Bytes,
+            backup: None,
+            renames: vec![sql::Rename::table("users=order").unwrap()],
+            confirm: None,
+        };
+
+        let mut renamed = code.to_string();
+        apply_edits(&mut renamed, rename.file_edits(code, "foo.py"));
+        assert_eq!(
+            renamed,
+            r#"
+crs.execute("SELECT id FROM \"order\"")
+crs.execute('SELECT id FROM "order"')
+crs.execute("""SELECT id FROM "order\"""")
+crs.execute(f"SELECT {col} FROM \"order\"")
+crs.execute(r"SELECT id FROM users")
+"#
+        );
+        assert!(in_replacement_field("a {{b}} {c:{d}}", 10));
+        assert!(!in_replacement_field("a {{b}} {c:{d}} e", 16));
+    }
+}
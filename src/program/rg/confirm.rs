@@ -0,0 +1,186 @@
+use std::str::FromStr;
+
+use console::{self, pad_str, style, Term};
+use textwrap::wrap;
+
+use super::utils::{CodeDiff, MatchRange};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ConfirmAns {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+impl FromStr for ConfirmAns {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "y" => Ok(ConfirmAns::Yes),
+            "n" => Ok(ConfirmAns::No),
+            "a" => Ok(ConfirmAns::All),
+            "q" => Ok(ConfirmAns::Quit),
+            _ => Err(anyhow::anyhow!("failed to parse confirmation input")),
+        }
+    }
+}
+
+impl ConfirmAns {
+    fn as_prompt() -> String {
+        format!(
+            "
+    {y} yes; make this change.
+    {n} no; skip this match.
+    {a} all; make this change and all remaining ones without further confirmation.
+    {q} quit; don't make any more changes.",
+            y = style("y").green(),
+            n = style("n").red(),
+            a = style("a").yellow(),
+            q = style("q").cyan()
+        )
+    }
+}
+
+/// Side by side BEFORE/AFTER prompt for each change in `--confirm` mode.
+pub struct Confirm {
+    term: Term,
+    context_lines: usize,
+    pub last_ans: Option<ConfirmAns>,
+}
+
+impl Confirm {
+    pub fn new(context_lines: usize) -> Self {
+        let term = Term::stdout();
+        term.hide_cursor().unwrap();
+
+        Self {
+            term,
+            context_lines,
+            last_ans: None,
+        }
+    }
+
+    /// Whether every remaining change has already been accepted.
+    pub fn accept_all(&self) -> bool {
+        matches!(self.last_ans, Some(ConfirmAns::All))
+    }
+
+    pub fn quit(&self) -> bool {
+        matches!(self.last_ans, Some(ConfirmAns::Quit))
+    }
+
+    pub fn finish(&self) {
+        self.term.show_cursor().unwrap();
+    }
+
+    /// Show the change at `rng` and wait for an answer. `description` is printed below the
+    /// match location.
+    pub fn ask(
+        &mut self,
+        path: &str,
+        rng: &MatchRange,
+        description: &str,
+        before: CodeDiff,
+        after: CodeDiff,
+    ) -> anyhow::Result<ConfirmAns> {
+        self.term.clear_screen()?;
+        let max_length = (self.term.size().1 as usize / 2) - 2;
+
+        println!("{}", self.header(path, rng, description, max_length));
+
+        self.print_before_after_sep();
+
+        let left_text = before
+            .trim_context_lines(self.context_lines)
+            .with_diff_color(console::Color::Red)
+            .replace("\r\n", "\n");
+        let right_text = after
+            .trim_context_lines(self.context_lines)
+            .with_diff_color(console::Color::Green)
+            .replace("\r\n", "\n");
+
+        let lines_left = wrap(&left_text, max_length)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let lines_right = wrap(&right_text, max_length)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let max_lines = std::cmp::max(lines_left.len(), lines_right.len());
+
+        for i in 0..max_lines {
+            let default = String::from("");
+            let left_line = lines_left.get(i).unwrap_or(&default);
+            let right_line = lines_right.get(i).unwrap_or(&default);
+            let left_line = pad_str(left_line, max_length, console::Alignment::Left, None);
+            let right_line = pad_str(right_line, max_length, console::Alignment::Left, None);
+            println!("{} │ {}", left_line, right_line);
+        }
+
+        self.print_sep("");
+        println!("{}", ConfirmAns::as_prompt());
+
+        let ans = self.get_answer()?;
+        self.last_ans = Some(ans);
+        Ok(ans)
+    }
+
+    fn print_sep(&self, title: &str) {
+        let title = if title.is_empty() {
+            "".into()
+        } else {
+            format!(" {} ", title)
+        };
+        println!(
+            "{title:━^width$}",
+            title = style(title).bold(),
+            width = self.term.size().1 as usize
+        );
+    }
+
+    fn header(&self, path: &str, rng: &MatchRange, description: &str, max_length: usize) -> String {
+        let match_info = format!(
+            "{}:{}:{}",
+            style(path).magenta(),
+            style((rng.start_point.row + 1).to_string()).green(),
+            rng.column,
+        );
+        let match_info = pad_str(&match_info, max_length, console::Alignment::Left, None);
+
+        format!("{}\n{}", match_info, description)
+    }
+
+    fn print_before_after_sep(&self) {
+        let total_width = self.term.size().1 as usize;
+
+        let before = " BEFORE ";
+        let after = " AFTER ";
+
+        let remaining_width = total_width - before.len() - after.len();
+        let sep_width = remaining_width / 4;
+
+        let mid_sep = total_width - (sep_width * 2 + before.len() + after.len());
+
+        println!(
+            "{sep:━<sep1_width$}{title1}{sep:━<sep2_width$}{title2}{sep:━<sep3_width$}",
+            sep = "",
+            title1 = style(before).bold().blue(),
+            title2 = style(after).bold().blue(),
+            sep1_width = sep_width,
+            sep2_width = mid_sep,
+            sep3_width = sep_width,
+        );
+    }
+
+    fn get_answer(&mut self) -> anyhow::Result<ConfirmAns> {
+        loop {
+            let ans = self.term.read_char()?.to_string().parse::<ConfirmAns>();
+            if let Ok(ans) = ans {
+                return Ok(ans);
+            }
+        }
+    }
+}
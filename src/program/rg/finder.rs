@@ -1,15 +1,16 @@
 use std::ops::Range;
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::str::FromStr;
 
-use console::{self, pad_str, style, Term};
+use console::{self, style};
 use regex::{self, Regex};
-use textwrap::wrap;
 
+use super::confirm::{Confirm, ConfirmAns};
 use super::utils::*;
-use crate::cli::RegexOptions;
-use crate::treesitter::TreesitterQuery;
+use crate::cli::{ColumnUnit, GlobalOptions, RegexOptions};
+use crate::lint::{BaselineMode, Suppressions};
+use crate::sql::{fingerprint, split_statements};
+use crate::treesitter::{SqlBlock, TreesitterQuery};
 use crate::utils::*;
 
 pub enum FindChoice {
@@ -18,7 +19,7 @@ pub enum FindChoice {
 }
 
 pub trait Finder {
-    fn new_finder(rg_opts: &RegexOptions) -> Self
+    fn new_finder(rg_opts: &RegexOptions, global: &GlobalOptions) -> Self
     where
         Self: Sized;
 
@@ -34,78 +35,156 @@ pub trait Finder {
         }
     }
     fn find_in_file(&mut self, ts: &mut Box<dyn TreesitterQuery>, file: FileState) -> FindChoice;
+
+    /// Called once every file has been searched.
+    fn finish(&mut self) {}
+}
+
+/// Rule id matching regex results in `sql-parse: ignore[...]` comments.
+pub const REGEX_RULE: &str = "regex";
+
+fn block_range(block: &SqlBlock) -> Range<usize> {
+    block.string_start.byte_range.start..block.string_end.byte_range.end
+}
+
+fn print_unused_ignores(file: &FileState, suppressions: &Suppressions, unit: ColumnUnit) {
+    for finding in suppressions.unused(&[REGEX_RULE]) {
+        let (line, col) = line_col(&file.code, finding.range.start, unit);
+        print(&file.path, line, Some(col), &finding.display());
+    }
 }
 
 pub struct PlainSearch {
     re: Regex,
+    class: Option<TokenClass>,
+    report_unused_ignores: bool,
+    baseline: BaselineMode,
+    column_unit: ColumnUnit,
 }
 
 impl Finder for PlainSearch {
-    fn new_finder(rg_opts: &RegexOptions) -> Self {
+    fn new_finder(rg_opts: &RegexOptions, global: &GlobalOptions) -> Self {
         Self {
             re: make_regex(rg_opts),
+            class: TokenClass::from_opts(rg_opts),
+            report_unused_ignores: rg_opts.report_unused_ignores,
+            baseline: BaselineMode::new(rg_opts.baseline.clone(), rg_opts.write_baseline.clone()),
+            column_unit: global.column_unit,
         }
     }
 
     fn find_in_file(&mut self, ts: &mut Box<dyn TreesitterQuery>, file: FileState) -> FindChoice {
-        for block in ts.sql_blocks(&file.code) {
+        let blocks = ts.sql_blocks(&file.code);
+        let mut suppressions = Suppressions::new(&file.code, &blocks);
+        for block in &blocks {
             let sql = block.inner_text(&file.code);
-            self.re
-                .find_iter(sql)
-                .map(|m| MatchRange::from_regex_match(&block, &m, &file.lines, &file.code))
+            let fingerprint = fingerprint(sql);
+            let regions = MatchRegions::new(sql, self.class);
+            regions
+                .find_iter(&self.re, sql)
+                .into_iter()
+                .filter(|_| {
+                    !suppressions.suppresses(&file.code, block_range(block), REGEX_RULE)
+                        && self
+                            .baseline
+                            .report(&file.path, &fingerprint, self.re.as_str())
+                })
+                .map(|m| {
+                    MatchRange::from_regex_match(
+                        block,
+                        &m,
+                        &file.lines,
+                        &file.code,
+                        self.column_unit,
+                    )
+                })
                 .for_each(|rng| {
                     let line =
                         CodeDiff::new_line(&file.code, &rng).with_diff_color(console::Color::Green);
-                    print(
-                        &file.path,
-                        rng.start_point.row + 1,
-                        Some(rng.start_point.column + 1),
-                        &line,
-                    )
+                    print(&file.path, rng.start_point.row + 1, Some(rng.column), &line)
                 });
         }
+        if self.report_unused_ignores {
+            print_unused_ignores(&file, &suppressions, self.column_unit);
+        }
         FindChoice::Continue
     }
+
+    fn finish(&mut self) {
+        self.baseline.finish();
+    }
 }
 
 pub struct InverseSearch {
     re: Regex,
+    class: Option<TokenClass>,
+    report_unused_ignores: bool,
+    baseline: BaselineMode,
+    column_unit: ColumnUnit,
 }
 impl Finder for InverseSearch {
-    fn new_finder(rg_opts: &RegexOptions) -> Self {
+    fn new_finder(rg_opts: &RegexOptions, global: &GlobalOptions) -> Self {
         Self {
             re: make_regex(rg_opts),
+            class: TokenClass::from_opts(rg_opts),
+            report_unused_ignores: rg_opts.report_unused_ignores,
+            baseline: BaselineMode::new(rg_opts.baseline.clone(), rg_opts.write_baseline.clone()),
+            column_unit: global.column_unit,
         }
     }
 
     fn find_in_file(&mut self, ts: &mut Box<dyn TreesitterQuery>, file: FileState) -> FindChoice {
-        for block in ts.sql_blocks(&file.code) {
+        let blocks = ts.sql_blocks(&file.code);
+        let mut suppressions = Suppressions::new(&file.code, &blocks);
+        for block in &blocks {
+            let offset = block.inner_text_range().start;
             let sql = block.inner_text(&file.code);
 
-            // maybe check line by line?
-            if !self.re.is_match(sql) {
-                print(
-                    &file.path,
-                    block.start_line_num(),
-                    None,
-                    block.inner_text(&file.code),
-                );
+            // each statement of a multi-statement string is reported on its own line
+            for range in split_statements(sql) {
+                let statement = &sql[range.clone()];
+                let regions = MatchRegions::new(statement, self.class);
+                let matched = !regions.find_iter(&self.re, statement).is_empty();
+                if !matched
+                    && !suppressions.suppresses(&file.code, block_range(block), REGEX_RULE)
+                    && self
+                        .baseline
+                        .report(&file.path, &fingerprint(statement), self.re.as_str())
+                {
+                    print(
+                        &file.path,
+                        line_number(&file.code, offset + range.start),
+                        None,
+                        statement,
+                    );
+                }
             }
         }
+        if self.report_unused_ignores {
+            print_unused_ignores(&file, &suppressions, self.column_unit);
+        }
         FindChoice::Continue
     }
+
+    fn finish(&mut self) {
+        self.baseline.finish();
+    }
 }
 
 pub struct Replace {
     re: Regex,
     replace_text: String,
+    class: Option<TokenClass>,
+    backup: Option<String>,
 }
 
 impl Finder for Replace {
-    fn new_finder(rg_opts: &RegexOptions) -> Self {
+    fn new_finder(rg_opts: &RegexOptions, global: &GlobalOptions) -> Self {
         Self {
             re: make_regex(rg_opts),
             replace_text: rg_opts.replace.as_ref().unwrap().to_owned(),
+            class: TokenClass::from_opts(rg_opts),
+            backup: global.backup.clone(),
         }
     }
 
@@ -114,17 +193,28 @@ impl Finder for Replace {
         ts: &mut Box<dyn TreesitterQuery>,
         mut file: FileState,
     ) -> FindChoice {
-        let mut change_count = 0;
+        let mut edits = Vec::new();
+        let blocks = ts.sql_blocks(&file.code);
+        let mut suppressions = Suppressions::new(&file.code, &blocks);
+        for block in &blocks {
+            let offset = block.inner_text_range().start;
+            let sql = block.inner_text(&file.code);
+            let regions = MatchRegions::new(sql, self.class);
+            for caps in regions.captures_iter(&self.re, sql) {
+                let m = caps.get(0).unwrap();
+                if suppressions.suppresses(&file.code, block_range(block), REGEX_RULE) {
+                    continue;
+                }
+                let mut new = String::new();
+                caps.expand(&self.replace_text, &mut new);
+                edits.push((offset + m.start()..offset + m.end(), new));
+            }
+        }
 
-        ts.sql_blocks(&file.code).iter().rev().for_each(|block| {
-            let inner_text_range = block.inner_text_range();
-            let sql = &file.code[inner_text_range.clone()];
-            let new_inner_text = self.re.replace_all(sql, &self.replace_text).into_owned();
-            file.code.replace_range(inner_text_range, &new_inner_text);
-            change_count += 1;
-        });
+        let change_count = edits.len();
+        apply_edits(&mut file.code, edits);
 
-        if write_file(&file.path, file.code.as_bytes()).is_err() {
+        if change_count > 0 && write_code(&file.path, &file.code, self.backup.as_deref()).is_err() {
             eprintln!("Failed to write to path: {}", file.path);
         }
         println!("{change_count} changes made to {}", file.path);
@@ -132,150 +222,19 @@ impl Finder for Replace {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum ConfirmAns {
-    Yes,
-    No,
-    All,
-    Quit,
-}
-
-impl FromStr for ConfirmAns {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "y" => Ok(ConfirmAns::Yes),
-            "n" => Ok(ConfirmAns::No),
-            "a" => Ok(ConfirmAns::All),
-            "q" => Ok(ConfirmAns::Quit),
-            _ => Err(anyhow::anyhow!("failed to parse confirmation input")),
-        }
-    }
-}
-
-impl ConfirmAns {
-    fn as_prompt() -> String {
-        format!(
-            "
-    {y} yes; make this change.
-    {n} no; skip this match.
-    {a} all; make this change and all remaining ones without further confirmation.
-    {q} quit; don't make any more changes.",
-            y = style("y").green(),
-            n = style("n").red(),
-            a = style("a").yellow(),
-            q = style("q").cyan()
-        )
-    }
-}
-
-struct DiffData<'a> {
-    sql_code: &'a str,
-    rng: &'a MatchRange,
-}
-
-impl<'a> DiffData<'a> {
-    fn new(sql_code: &'a str, rng: &'a MatchRange) -> Self {
-        Self { sql_code, rng }
-    }
-}
-
 pub struct ReplaceConfirm {
     re: Regex,
     replace_text: String,
-    last_ans: Option<ConfirmAns>,
-    term: Term,
-    context_lines: usize,
+    class: Option<TokenClass>,
+    confirm: Confirm,
+    column_unit: ColumnUnit,
+    backup: Option<String>,
 }
 
 impl ReplaceConfirm {
-    fn left_side_diff(&self, sql_code: &str, rng: &MatchRange) -> String {
-        CodeDiff::new_block(sql_code, rng)
-            .trim_context_lines(self.context_lines)
-            .with_diff_color(console::Color::Red)
-            .replace("\r\n", "\n")
-    }
-
-    fn right_side_diff(&self, sql_code: &str, rng: &MatchRange) -> String {
-        let (before, diff, after) = replace_in_range_partitioned(
-            &self.re,
-            sql_code,
-            rng.block_match_range(),
-            &self.replace_text,
-        );
-        CodeDiff::new_raw(&before, &diff, &after)
-            .trim_context_lines(self.context_lines)
-            .with_diff_color(console::Color::Green)
-            .replace("\r\n", "\n")
-    }
-
-    fn print_confirm(
-        &mut self,
-        path: &str,
-        left: DiffData,
-        right: DiffData,
-    ) -> anyhow::Result<ConfirmAns> {
-        self.term.clear_screen()?;
-        let max_length = (self.term.size().1 as usize / 2) - 2;
-
-        println!("{}", self.header(path, &left, max_length));
-
-        self.print_before_after_sep();
-
-        let left_text = self.left_side_diff(left.sql_code, left.rng);
-        let right_text = self.right_side_diff(right.sql_code, right.rng);
-
-        let lines_left = wrap(&left_text, max_length)
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
-        let lines_right = wrap(&right_text, max_length)
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
-        let max_lines = std::cmp::max(lines_left.len(), lines_right.len());
-
-        for i in 0..max_lines {
-            let default = String::from("");
-            let left_line = lines_left.get(i).unwrap_or(&default);
-            let right_line = lines_right.get(i).unwrap_or(&default);
-            let left_line = pad_str(left_line, max_length, console::Alignment::Left, None);
-            let right_line = pad_str(right_line, max_length, console::Alignment::Left, None);
-            println!("{} │ {}", left_line, right_line);
-        }
-
-        self.print_sep("");
-        println!("{}", ConfirmAns::as_prompt());
-
-        self.get_answer()
-    }
-
-    fn print_sep(&self, title: &str) {
-        let title = if title.is_empty() {
-            "".into()
-        } else {
-            format!(" {} ", title)
-        };
-        println!(
-            "{title:━^width$}",
-            title = style(title).bold(),
-            width = self.term.size().1 as usize
-        );
-    }
-
-    fn header(&self, path: &str, left: &DiffData, max_length: usize) -> String {
-        let match_info = format!(
-            "{}:{}:{}",
-            style(path).magenta(),
-            style((left.rng.start_point.row + 1).to_string()).green(),
-            left.rng.start_point.column + 1,
-        );
-        let match_info = pad_str(&match_info, max_length, console::Alignment::Left, None);
-
+    fn description(&self) -> String {
         format!(
-            "{}\n{}: {}\n{}: {}",
-            match_info,
+            "{}: {}\n{}: {}",
             style("Search Pattern").red(),
             self.re,
             style("Replace Pattern").green(),
@@ -283,61 +242,36 @@ impl ReplaceConfirm {
         )
     }
 
-    fn print_before_after_sep(&self) {
-        let total_width = self.term.size().1 as usize;
-
-        let before = " BEFORE ";
-        let after = " AFTER ";
-
-        let remaining_width = total_width - before.len() - after.len();
-        let sep_width = remaining_width / 4;
-
-        let mid_sep = total_width - (sep_width * 2 + before.len() + after.len());
-
-        println!(
-            "{sep:━<sep1_width$}{title1}{sep:━<sep2_width$}{title2}{sep:━<sep3_width$}",
-            sep = "",
-            title1 = style(before).bold().blue(),
-            title2 = style(after).bold().blue(),
-            sep1_width = sep_width,
-            sep2_width = mid_sep,
-            sep3_width = sep_width,
-        );
-    }
-
-    fn get_answer(&mut self) -> anyhow::Result<ConfirmAns> {
-        loop {
-            let ans = self.term.read_char()?.to_string().parse::<ConfirmAns>();
-            if let Ok(ans) = ans {
-                return Ok(ans);
-            }
-        }
-    }
-
     fn process_replacements(&mut self, replacements: Vec<Range<usize>>, mut file: FileState) {
-        for rng in replacements.iter().rev() {
-            let rng = rng.to_owned();
-            let mut _match = &file.code[rng.clone()];
-            let new = self.re.replace_all(_match, &self.replace_text).into_owned();
-            file.code.replace_range(rng, &new);
+        if replacements.is_empty() {
+            return;
         }
-        if write_file(&file.path, file.code.as_bytes()).is_err() {
+        let edits = replacements
+            .into_iter()
+            .map(|rng| {
+                let new = self
+                    .re
+                    .replace_all(&file.code[rng.clone()], &self.replace_text)
+                    .into_owned();
+                (rng, new)
+            })
+            .collect();
+        apply_edits(&mut file.code, edits);
+        if write_code(&file.path, &file.code, self.backup.as_deref()).is_err() {
             eprintln!("Failed to write to path: {}", file.path);
         }
     }
 }
 
 impl Finder for ReplaceConfirm {
-    fn new_finder(rg_opts: &RegexOptions) -> Self {
-        let term = Term::stdout();
-        term.hide_cursor().unwrap();
-
+    fn new_finder(rg_opts: &RegexOptions, global: &GlobalOptions) -> Self {
         Self {
             re: make_regex(rg_opts),
             replace_text: rg_opts.replace.as_ref().unwrap().to_owned(),
-            last_ans: None,
-            term,
-            context_lines: rg_opts.context,
+            class: TokenClass::from_opts(rg_opts),
+            confirm: Confirm::new(rg_opts.context),
+            column_unit: global.column_unit,
+            backup: global.backup.clone(),
         }
     }
 
@@ -353,12 +287,15 @@ impl Finder for ReplaceConfirm {
                 break;
             }
         }
-        self.term.show_cursor().unwrap();
+        self.confirm.finish();
     }
 
     fn find_in_file(&mut self, ts: &mut Box<dyn TreesitterQuery>, file: FileState) -> FindChoice {
         let mut replacements = Vec::new();
-        'outer: for block in ts.sql_blocks(&file.code) {
+        let description = self.description();
+        let blocks = ts.sql_blocks(&file.code);
+        let mut suppressions = Suppressions::new(&file.code, &blocks);
+        'outer: for block in &blocks {
             let inner_text_range = block.inner_text_range();
             let sql = &file.code[inner_text_range.clone()];
             let mut display_sql = sql.to_string();
@@ -366,22 +303,43 @@ impl Finder for ReplaceConfirm {
             let mut display_rng = MatchRange::default();
             let mut shift = 0;
 
-            let matches: Vec<_> = self.re.find_iter(sql).collect();
+            let regions = MatchRegions::new(sql, self.class);
+            let matches: Vec<_> = regions
+                .find_iter(&self.re, sql)
+                .into_iter()
+                .filter(|_| !suppressions.suppresses(&file.code, block_range(block), REGEX_RULE))
+                .collect();
             for mtch in matches {
-                let rng = MatchRange::from_regex_match(&block, &mtch, &file.lines, &file.code);
+                let rng = MatchRange::from_regex_match(
+                    block,
+                    &mtch,
+                    &file.lines,
+                    &file.code,
+                    self.column_unit,
+                );
                 display_rng = rng.shifted_ranged(shift);
 
-                if matches!(self.last_ans, Some(ConfirmAns::All)) {
+                if self.confirm.accept_all() {
                     replacements.push(rng.abs_match_range);
                     continue;
                 }
 
-                let left = DiffData::new(sql, &rng);
-                let right = DiffData::new(&display_sql, &display_rng);
+                let (before, diff, after) = replace_in_range_partitioned(
+                    &self.re,
+                    &display_sql,
+                    display_rng.block_match_range(),
+                    &self.replace_text,
+                );
                 let ans = self
-                    .print_confirm(&file.path, left, right)
+                    .confirm
+                    .ask(
+                        &file.path,
+                        &rng,
+                        &description,
+                        CodeDiff::new_block(sql, &rng),
+                        CodeDiff::new_raw(&before, &diff, &after),
+                    )
                     .expect("failed to print confirmation message or get response");
-                self.last_ans = Some(ans);
 
                 match ans {
                     ConfirmAns::Yes | ConfirmAns::All => {
@@ -403,7 +361,7 @@ impl Finder for ReplaceConfirm {
         }
 
         self.process_replacements(replacements, file);
-        if matches!(self.last_ans, Some(ConfirmAns::Quit)) {
+        if self.confirm.quit() {
             return FindChoice::Exit;
         }
         FindChoice::Continue
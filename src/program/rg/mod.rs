@@ -1,5 +1,6 @@
+pub(super) mod confirm;
 mod finder;
-mod utils;
+pub(super) mod utils;
 
 use std::path::PathBuf;
 use std::rc::Rc;
@@ -21,6 +22,7 @@ impl Program for Rg {
         let treesitter = ts_query_factory(&cli);
         let search_paths = Rc::new(cli.search_paths());
 
+        let global = cli.global;
         let rg_opts: RegexOptions = cli.command.into();
 
         let finder: Box<dyn Finder> = match (
@@ -28,10 +30,10 @@ impl Program for Rg {
             rg_opts.replace.as_ref(),
             rg_opts.confirm,
         ) {
-            (false, None, _) => Box::new(PlainSearch::new_finder(&rg_opts)),
-            (false, Some(_), false) => Box::new(Replace::new_finder(&rg_opts)),
-            (false, Some(_), true) => Box::new(ReplaceConfirm::new_finder(&rg_opts)),
-            (true, _, _) => Box::new(InverseSearch::new_finder(&rg_opts)),
+            (false, None, _) => Box::new(PlainSearch::new_finder(&rg_opts, &global)),
+            (false, Some(_), false) => Box::new(Replace::new_finder(&rg_opts, &global)),
+            (false, Some(_), true) => Box::new(ReplaceConfirm::new_finder(&rg_opts, &global)),
+            (true, _, _) => Box::new(InverseSearch::new_finder(&rg_opts, &global)),
         };
 
         Self {
@@ -44,5 +46,6 @@ impl Program for Rg {
     fn run(&mut self) {
         self.finder
             .find(&mut self.treesitter, self.search_paths.clone());
+        self.finder.finish();
     }
 }
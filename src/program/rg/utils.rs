@@ -1,11 +1,12 @@
 use std::borrow::Cow;
 use std::ops::Range;
 
-use regex::{self, Match, Regex, RegexBuilder, Replacer};
+use regex::{self, Captures, Match, Regex, RegexBuilder, Replacer};
 use tree_sitter::Point;
 
-use crate::cli::RegexOptions;
+use crate::cli::{ColumnUnit, RegexOptions};
 use crate::error_exit;
+use crate::sql::lexer::{tokenize, Token, TokenKind};
 use crate::treesitter::SqlBlock;
 
 pub fn make_regex(rg_opts: &RegexOptions) -> Regex {
@@ -31,6 +32,117 @@ pub fn make_regex(rg_opts: &RegexOptions) -> Regex {
         .unwrap_or_else(|err| error_exit!("Failed to build regex:\n{}", err))
 }
 
+/// SQL tokens regex matches are restricted to by `--code-only` and friends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Code,
+    Comments,
+    Literals,
+}
+
+impl TokenClass {
+    pub fn from_opts(rg_opts: &RegexOptions) -> Option<Self> {
+        match (
+            rg_opts.code_only,
+            rg_opts.comments_only,
+            rg_opts.literals_only,
+        ) {
+            (true, _, _) => Some(Self::Code),
+            (_, true, _) => Some(Self::Comments),
+            (_, _, true) => Some(Self::Literals),
+            _ => None,
+        }
+    }
+
+    fn selects(&self, token: &Token) -> bool {
+        let is_code = !matches!(token.kind, TokenKind::Comment | TokenKind::String);
+        match self {
+            Self::Code => is_code,
+            Self::Comments => token.kind == TokenKind::Comment,
+            Self::Literals => token.kind == TokenKind::String,
+        }
+    }
+}
+
+/// Parts of a SQL string a match has to fall entirely within.
+pub struct MatchRegions {
+    regions: Option<Vec<Range<usize>>>,
+}
+
+impl MatchRegions {
+    pub fn new(sql: &str, class: Option<TokenClass>) -> Self {
+        let Some(class) = class else {
+            return Self { regions: None };
+        };
+
+        let mut regions: Vec<Range<usize>> = Vec::new();
+        for token in tokenize(sql).iter().filter(|tok| class.selects(tok)) {
+            match regions.last_mut() {
+                // runs of code are searched as a whole so matches can span several tokens
+                Some(last) if class == TokenClass::Code && last.end == token.range.start => {
+                    last.end = token.range.end
+                }
+                _ => regions.push(token.range.clone()),
+            }
+        }
+        Self {
+            regions: Some(regions),
+        }
+    }
+
+    /// Matches of `re` in `sql` that lie within a region. Each region is searched on its own
+    /// so an excluded region only cuts matches short rather than hiding the ones after it.
+    pub fn find_iter<'h>(&self, re: &Regex, sql: &'h str) -> Vec<Match<'h>> {
+        self.search(sql, |haystack, start| {
+            re.find_at(haystack, start).map(|m| (m, m.range()))
+        })
+    }
+
+    /// Like [`MatchRegions::find_iter`] with capture groups.
+    pub fn captures_iter<'h>(&self, re: &Regex, sql: &'h str) -> Vec<Captures<'h>> {
+        self.search(sql, |haystack, start| {
+            re.captures_at(haystack, start).map(|caps| {
+                let range = caps.get(0).unwrap().range();
+                (caps, range)
+            })
+        })
+    }
+
+    fn search<'h, T>(
+        &self,
+        sql: &'h str,
+        mut find_at: impl FnMut(&'h str, usize) -> Option<(T, Range<usize>)>,
+    ) -> Vec<T> {
+        let whole = 0..sql.len();
+        let regions = match &self.regions {
+            Some(regions) => regions.as_slice(),
+            None => std::slice::from_ref(&whole),
+        };
+
+        let mut found = Vec::new();
+        for region in regions {
+            // cutting the haystack at the region's end while searching from its start keeps
+            // anchors and word boundaries seeing the text before the region
+            let haystack = &sql[..region.end];
+            let mut start = region.start;
+            while start <= region.end {
+                let Some((item, range)) = find_at(haystack, start) else {
+                    break;
+                };
+                start = match range.is_empty() {
+                    true => match haystack[range.end..].chars().next() {
+                        Some(ch) => range.end + ch.len_utf8(),
+                        None => region.end + 1,
+                    },
+                    false => range.end,
+                };
+                found.push(item);
+            }
+        }
+        found
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct FileState {
     pub path: String,
@@ -42,7 +154,10 @@ pub struct FileState {
 pub struct MatchRange {
     pub abs_match_range: Range<usize>,
     pub block_match_range: Range<usize>,
+    /// Row and byte column of the match start.
     pub start_point: Point,
+    /// 1-based column of the match start in the `--column-unit`, for display.
+    pub column: usize,
     pub abs_line_range: Range<usize>,
     pub line_match_range: Range<usize>,
 }
@@ -53,6 +168,7 @@ impl std::fmt::Debug for MatchRange {
         writeln!(f, "  abs_match_range: {:?},", self.abs_match_range)?;
         writeln!(f, "  block_match_range: {:?},", self.block_match_range)?;
         writeln!(f, "  start_point: {:?},", self.start_point)?;
+        writeln!(f, "  column: {:?},", self.column)?;
         writeln!(f, "  abs_line_range: {:?},", self.abs_line_range)?;
         writeln!(f, "  line_match_range: {:?},", self.line_match_range)?;
         writeln!(f, "}}")
@@ -65,18 +181,31 @@ impl MatchRange {
         regex_match: &Match,
         lines: &[usize],
         code: &str,
+        unit: ColumnUnit,
     ) -> Self {
-        let block_match_start = regex_match.start();
-        let block_match_end = regex_match.end();
+        let block_offset = ts_block.string_start.byte_range.end;
+        let abs_range = block_offset + regex_match.start()..block_offset + regex_match.end();
 
-        let abs_start = ts_block.string_start.byte_range.end + block_match_start;
-        let abs_end = ts_block.string_start.byte_range.end + block_match_end;
+        Self {
+            block_match_range: regex_match.range(),
+            ..Self::from_abs_range(abs_range, lines, code, unit)
+        }
+    }
+
+    /// Range of a match given by its absolute byte range within `code`, with its column counted
+    /// in `unit`.
+    pub fn from_abs_range(
+        abs_range: Range<usize>,
+        lines: &[usize],
+        code: &str,
+        unit: ColumnUnit,
+    ) -> Self {
+        let abs_start = abs_range.start;
+        let abs_end = abs_range.end;
 
         let row = lines
             .iter()
-            .rposition(|&line_byte| {
-                line_byte <= block_match_start + ts_block.string_start.byte_range.end
-            })
+            .rposition(|&line_byte| line_byte <= abs_start)
             .unwrap_or(0);
 
         let abs_line_start = lines[row];
@@ -99,10 +228,11 @@ impl MatchRange {
 
         Self {
             abs_match_range: abs_start..abs_end,
-            block_match_range: block_match_start..block_match_end,
+            block_match_range: abs_start..abs_end,
             start_point: Point { row, column },
+            column: unit.column(&code[abs_line_start..abs_start]),
             abs_line_range: abs_line_start..abs_line_end,
-            line_match_range: column..(column + block_match_end - block_match_start),
+            line_match_range: column..(column + abs_end - abs_start),
         }
     }
 
@@ -117,6 +247,7 @@ impl MatchRange {
             abs_match_range: adjust_range(self.abs_match_range(), chars),
             block_match_range: adjust_range(self.block_match_range(), chars),
             start_point: self.start_point, // bad bad bad but w/e
+            column: self.column,
             abs_line_range: adjust_range(self.abs_line_range(), chars),
             line_match_range: adjust_range(self.line_match_range(), chars),
         }
@@ -233,15 +364,24 @@ impl<'a> CodeDiff<'a> {
     }
 }
 
+/// Byte offsets of the start of each line of `code`.
 pub fn block_lines(code: &str) -> Vec<usize> {
     let mut lines = vec![0];
-    code.chars()
+    code.bytes()
         .enumerate()
-        .filter(|(_, ch)| *ch == '\n')
+        .filter(|(_, byte)| *byte == b'\n')
         .for_each(|(idx, _)| lines.push(idx + 1));
     lines
 }
 
+/// Replace each range of `code`, starting from the end so the earlier ranges stay valid.
+pub fn apply_edits(code: &mut String, mut edits: Vec<(Range<usize>, String)>) {
+    edits.sort_by_key(|(range, _)| range.start);
+    for (range, text) in edits.into_iter().rev() {
+        code.replace_range(range, &text);
+    }
+}
+
 pub fn replace_in_range<'h, R: Replacer>(
     regex: &Regex,
     haystack: &'h str,
@@ -299,7 +439,7 @@ mod test {
         let re = regex::Regex::new(re_str).unwrap();
         let m = re.find(sql).expect("not testing the lack of matches");
         let lines = block_lines(input);
-        MatchRange::from_regex_match(&block, &m, &lines, input)
+        MatchRange::from_regex_match(&block, &m, &lines, input, ColumnUnit::Bytes)
     }
 
     mod match_range {
@@ -335,6 +475,7 @@ mod test {
                 abs_match_range: 13..19,
                 block_match_range: 0..6,
                 start_point: Point { row: 0, column: 13 },
+                column: 14,
                 abs_line_range: 0..40,
                 line_match_range: 13..19,
             };
@@ -350,6 +491,7 @@ SELECT 'hi'""";)"#;
                 abs_match_range: 15..21,
                 block_match_range: 0..6,
                 start_point: Point { row: 0, column: 15 },
+                column: 16,
                 abs_line_range: 0..27,
                 line_match_range: 15..21,
             };
@@ -368,6 +510,7 @@ SELECT 'hi'""";)"#;
                 abs_match_range: 16..22,
                 block_match_range: 1..7,
                 start_point: Point { row: 1, column: 0 },
+                column: 1,
                 abs_line_range: 16..28,
                 line_match_range: 0..6,
             };
@@ -385,6 +528,7 @@ SELECT 'hi'""";)"#;
                 abs_match_range: 15..25,
                 block_match_range: 0..10,
                 start_point: Point { row: 0, column: 15 },
+                column: 16,
                 abs_line_range: 0..31,
                 line_match_range: 15..25,
             };
@@ -403,12 +547,37 @@ SELECT 'hi'""";)"#;
                 abs_match_range: 16..26,
                 block_match_range: 1..11,
                 start_point: Point { row: 1, column: 0 },
+                column: 1,
                 abs_line_range: 16..32,
                 line_match_range: 0..10,
             };
             assert_rng!(rng, expected, input);
             assert_eq!(rng.match_length(), 10);
         }
+
+        #[test]
+        fn non_ascii_before_match() {
+            let input = "# café ☕\ncrs.execute(\"SELECT 'é'; DROP t\")";
+            let rng = exec_get_first_rng(input, "DROP");
+            let expected = MatchRange {
+                abs_match_range: 38..42,
+                block_match_range: 13..17,
+                start_point: Point { row: 1, column: 26 },
+                column: 27,
+                abs_line_range: 12..46,
+                line_match_range: 26..30,
+            };
+            assert_rng!(rng, expected, input);
+            assert_eq!(&input[rng.abs_match_range()], "DROP");
+            assert_eq!(CodeDiff::new_line(input, &rng).diff, "DROP");
+            assert_eq!(ColumnUnit::Chars.len("crs.execute(\"SELECT 'é'; "), 25);
+            assert_eq!(ColumnUnit::Utf16.len("☕𝄞"), 3);
+
+            let lines = block_lines(input);
+            let chars = MatchRange::from_abs_range(38..42, &lines, input, ColumnUnit::Chars);
+            assert_eq!(chars.column, 26);
+            assert_eq!(chars.start_point, rng.start_point);
+        }
     }
 
     mod code_diff {
@@ -459,6 +628,46 @@ SELECT 'hi';""")"#;
         assert_eq!(block_lines(input), expected);
     }
 
+    #[test]
+    fn match_regions() {
+        let sql = "DROP TABLE a -- never DROP TABLE here\nVALUES ('DROP TABLE')";
+        let re = Regex::new("DROP TABLE").unwrap();
+        let matches = |class| {
+            MatchRegions::new(sql, class)
+                .find_iter(&re, sql)
+                .iter()
+                .map(|m| m.start())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matches(None), vec![0, 22, 47]);
+        assert_eq!(matches(Some(TokenClass::Code)), vec![0]);
+        assert_eq!(matches(Some(TokenClass::Comments)), vec![22]);
+        assert_eq!(matches(Some(TokenClass::Literals)), vec![47]);
+    }
+
+    #[test]
+    fn match_regions_search_each_region() {
+        // the first match starts in the literal, hiding the one in the code after it
+        let sql = "'b' b c";
+        let re = Regex::new(r"b.*c").unwrap();
+        let regions = MatchRegions::new(sql, Some(TokenClass::Code));
+        let matches = regions
+            .find_iter(&re, sql)
+            .iter()
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(matches, vec!["b c"]);
+
+        let re = Regex::new(r"\b").unwrap();
+        let regions = MatchRegions::new("ab 'c'", Some(TokenClass::Literals));
+        let starts = regions
+            .captures_iter(&re, "ab 'c'")
+            .iter()
+            .map(|caps| caps.get(0).unwrap().start())
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![4, 5]);
+    }
+
     #[test]
     fn test_replace_in_range_1() {
         let regex = Regex::new(r"\d+").unwrap();
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use console::style;
+use serde::Serialize;
+
+use super::Program;
+use crate::cli::{Cli, OutputFormat, StatsOptions};
+use crate::sql::metrics;
+use crate::treesitter::{Exec, InterpolationKind};
+use crate::utils::*;
+
+pub(crate) struct Stats {
+    exec: Exec,
+    search_paths: Vec<PathBuf>,
+    format: OutputFormat,
+    top: usize,
+    report: Report,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    files: usize,
+    calls: usize,
+    blocks: usize,
+    statements: usize,
+    joins: usize,
+    max_joins: usize,
+    max_subquery_depth: usize,
+    /// Statement counts by leading verb.
+    kinds: BTreeMap<String, usize>,
+    /// `execute` calls building their SQL at runtime, by how it's built.
+    dynamic: BTreeMap<&'static str, usize>,
+    /// Blocks by string delimiter.
+    quote_styles: BTreeMap<String, usize>,
+    per_file: Vec<FileStats>,
+    longest: Vec<Query>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct FileStats {
+    path: String,
+    calls: usize,
+    blocks: usize,
+    statements: usize,
+    joins: usize,
+    max_subquery_depth: usize,
+    dynamic: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Query {
+    path: String,
+    line: usize,
+    lines: usize,
+    tokens: usize,
+}
+
+impl Program for Stats {
+    fn new(cli: Cli) -> Self {
+        let search_paths = cli.search_paths();
+        let opts: StatsOptions = cli.command.into();
+
+        Self {
+            exec: Exec::new(),
+            search_paths,
+            format: opts.format,
+            top: opts.top,
+            report: Report::default(),
+        }
+    }
+
+    fn run(&mut self) {
+        let search_paths = std::mem::take(&mut self.search_paths);
+        for (code, path) in iter_valid_files(&search_paths) {
+            self.add_file(&code, path.as_path().to_str().unwrap());
+        }
+        self.finish();
+
+        let result = match self.format {
+            OutputFormat::Text => {
+                self.print_text();
+                Ok(())
+            }
+            OutputFormat::Csv => self.print_csv(),
+            OutputFormat::Json => self.print_json(),
+        };
+        if let Err(err) = result {
+            eprintln!("Failed to write stats: {err}");
+        }
+    }
+}
+
+impl Stats {
+    fn add_file(&mut self, code: &str, path: &str) {
+        let report = &mut self.report;
+        let mut file = FileStats {
+            path: path.to_string(),
+            ..Default::default()
+        };
+
+        for call in self.exec.calls(code) {
+            file.calls += 1;
+            file.blocks += call.blocks.len();
+            for block in &call.blocks {
+                let delimiter = block.opening(code).map_or("", |(_, delimiter)| delimiter);
+                *report
+                    .quote_styles
+                    .entry(delimiter.to_string())
+                    .or_default() += 1;
+            }
+
+            if !call.interpolations.is_empty() {
+                file.dynamic += 1;
+                for kind in dedup_kinds(call.interpolations.iter().map(|i| i.kind)) {
+                    *report.dynamic.entry(kind).or_default() += 1;
+                }
+            }
+
+            let sql = call
+                .blocks
+                .iter()
+                .map(|block| block.inner_text(code))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let mut tokens = 0;
+            for statement in metrics(&sql) {
+                file.statements += 1;
+                file.joins += statement.joins;
+                file.max_subquery_depth = file.max_subquery_depth.max(statement.subquery_depth);
+                report.max_joins = report.max_joins.max(statement.joins);
+                *report.kinds.entry(statement.kind).or_default() += 1;
+                tokens += statement.tokens;
+            }
+            if tokens > 0 {
+                report.longest.push(Query {
+                    path: path.to_string(),
+                    line: call.sql_arg.point.row + 1,
+                    lines: line_number(code, call.sql_arg.byte_range.end) - call.sql_arg.point.row,
+                    tokens,
+                });
+            }
+        }
+
+        if file.calls == 0 {
+            return;
+        }
+        report.files += 1;
+        report.calls += file.calls;
+        report.blocks += file.blocks;
+        report.statements += file.statements;
+        report.joins += file.joins;
+        report.max_subquery_depth = report.max_subquery_depth.max(file.max_subquery_depth);
+        report.per_file.push(file);
+    }
+
+    /// Sort hotspots first and keep only the `top` longest queries.
+    fn finish(&mut self) {
+        let report = &mut self.report;
+        report
+            .per_file
+            .sort_by_key(|file| std::cmp::Reverse((file.statements, file.dynamic)));
+        report
+            .longest
+            .sort_by_key(|query| std::cmp::Reverse(query.tokens));
+        report.longest.truncate(self.top);
+    }
+
+    fn print_text(&self) {
+        let report = &self.report;
+        let heading = |title: &str| println!("{}", style(title).bold());
+        let row = |name: &str, value: usize| println!("    {name:<24} {value:>8}");
+
+        heading("Summary");
+        row("files", report.files);
+        row("execute calls", report.calls);
+        row("strings", report.blocks);
+        row("statements", report.statements);
+        row("joins", report.joins);
+        row("most joins", report.max_joins);
+        row("deepest subquery", report.max_subquery_depth);
+
+        heading("Statement kinds");
+        for (kind, count) in &report.kinds {
+            row(kind, *count);
+        }
+
+        heading("Dynamic SQL");
+        for (kind, count) in &report.dynamic {
+            row(kind, *count);
+        }
+
+        heading("Quote styles");
+        for (delimiter, count) in &report.quote_styles {
+            row(delimiter, *count);
+        }
+
+        heading("Files");
+        println!(
+            "    {:>6} {:>7} {:>10} {:>5} {:>5} {:>7}  path",
+            "calls", "strings", "statements", "joins", "depth", "dynamic"
+        );
+        for file in &report.per_file {
+            println!(
+                "    {:>6} {:>7} {:>10} {:>5} {:>5} {:>7}  {}",
+                file.calls,
+                file.blocks,
+                file.statements,
+                file.joins,
+                file.max_subquery_depth,
+                file.dynamic,
+                style(&file.path).magenta()
+            );
+        }
+
+        heading("Longest queries");
+        for query in &report.longest {
+            println!(
+                "    {:>6} tokens {:>4} lines  {}:{}",
+                query.tokens,
+                query.lines,
+                style(&query.path).magenta(),
+                style(query.line).green()
+            );
+        }
+    }
+
+    /// One row per file, for spreadsheets.
+    fn print_csv(&self) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        writer.write_record([
+            "path",
+            "calls",
+            "strings",
+            "statements",
+            "joins",
+            "max_subquery_depth",
+            "dynamic",
+        ])?;
+        for file in &self.report.per_file {
+            writer.write_record([
+                file.path.clone(),
+                file.calls.to_string(),
+                file.blocks.to_string(),
+                file.statements.to_string(),
+                file.joins.to_string(),
+                file.max_subquery_depth.to_string(),
+                file.dynamic.to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn print_json(&self) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(std::io::stdout(), &self.report)?;
+        println!();
+        Ok(())
+    }
+}
+
+fn dedup_kinds(kinds: impl Iterator<Item = InterpolationKind>) -> Vec<&'static str> {
+    let mut kinds = kinds
+        .map(|kind| match kind {
+            InterpolationKind::FString => "f-string",
+            InterpolationKind::Format => "format",
+            InterpolationKind::Concat => "concatenation",
+            InterpolationKind::Percent => "percent",
+        })
+        .collect::<Vec<_>>();
+    kinds.sort();
+    kinds.dedup();
+    kinds
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let code = r#"
+crs.execute("SELECT * FROM a JOIN b ON a.id = b.id; DELETE FROM c")
+crs.execute(f"""
+    SELECT * FROM {table}
+    WHERE id IN (SELECT id FROM d)
+""")
+crs.execute('UPDATE e SET x = 1 WHERE id = ' + str(id))
+"#;
+        let mut stats = Stats {
+            exec: Exec::new(),
+            search_paths: Vec::new(),
+            format: OutputFormat::Json,
+            top: 2,
+            report: Report::default(),
+        };
+        stats.add_file(code, "foo.py");
+        stats.finish();
+
+        let report = serde_json::to_value(&stats.report).unwrap();
+        assert_eq!(report["calls"], 3);
+        assert_eq!(report["statements"], 4);
+        assert_eq!(report["joins"], 1);
+        assert_eq!(report["max_subquery_depth"], 1);
+        assert_eq!(
+            report["kinds"],
+            serde_json::json!({"DELETE": 1, "SELECT": 2, "UPDATE": 1})
+        );
+        assert_eq!(
+            report["dynamic"],
+            serde_json::json!({"concatenation": 1, "f-string": 1})
+        );
+        assert_eq!(
+            report["quote_styles"],
+            serde_json::json!({"\"": 1, "\"\"\"": 1, "'": 1})
+        );
+        let longest = stats
+            .report
+            .longest
+            .iter()
+            .map(|query| (query.line, query.lines))
+            .collect::<Vec<_>>();
+        assert_eq!(longest, vec![(2, 1), (3, 4)]);
+    }
+}
@@ -1,20 +1,27 @@
 use std::path::PathBuf;
 
 use super::Program;
-use crate::cli::Cli;
+use crate::cli::{Cli, TsOptions};
+use crate::sql::split_statements;
 use crate::treesitter::{ts_query_factory, TreesitterQuery};
 use crate::utils::*;
 
 pub(crate) struct Treesitter {
     treesitter: Box<dyn TreesitterQuery>,
     search_paths: Vec<PathBuf>,
+    split_statements: bool,
 }
 
 impl Program for Treesitter {
     fn new(cli: Cli) -> Self {
+        let treesitter = ts_query_factory(&cli);
+        let search_paths = cli.search_paths();
+        let opts: TsOptions = cli.command.into();
+
         Self {
-            treesitter: ts_query_factory(&cli),
-            search_paths: cli.search_paths(),
+            treesitter,
+            search_paths,
+            split_statements: opts.split_statements,
         }
     }
 
@@ -22,7 +29,17 @@ impl Program for Treesitter {
         for (code, path) in iter_valid_files(&self.search_paths) {
             let path = path.as_path().to_str().unwrap();
             for block in self.treesitter.sql_blocks(&code) {
-                print(path, block.start_line_num(), None, block.inner_text(&code));
+                if !self.split_statements {
+                    print(path, block.start_line_num(), None, block.inner_text(&code));
+                    continue;
+                }
+
+                let offset = block.inner_text_range().start;
+                let sql = block.inner_text(&code);
+                for range in split_statements(sql) {
+                    let line = line_number(&code, offset + range.start);
+                    print(path, line, None, &sql[range]);
+                }
             }
         }
     }
@@ -0,0 +1,107 @@
+use super::Program;
+use crate::cli::Cli;
+use crate::error_exit;
+use crate::journal::{unchanged, Journal, JOURNAL_DIR};
+use crate::utils::write_file;
+
+pub(crate) struct Undo {
+    journal: Journal,
+}
+
+impl Program for Undo {
+    fn new(_cli: Cli) -> Self {
+        let journal = Journal::load().unwrap_or_else(|err| {
+            error_exit!("Nothing to undo, failed to read {}: {}", JOURNAL_DIR, err)
+        });
+        Self { journal }
+    }
+
+    fn run(&mut self) {
+        for entry in &self.journal.entries {
+            let path = entry.path.display();
+            match std::fs::read(&entry.path) {
+                Ok(current) if !unchanged(entry, &current) => {
+                    eprintln!("Changed since the last run: {path} -- skipping");
+                    continue;
+                }
+                Err(_) if entry.original.is_some() => {
+                    eprintln!("Failed to read file: {path} -- skipping");
+                    continue;
+                }
+                // a created file that's already gone
+                Err(_) => continue,
+                _ => {}
+            }
+
+            let restored = match self.journal.original(entry) {
+                Ok(Some(original)) => write_file(&entry.path, &original).map(|_| "Restored"),
+                Ok(None) => std::fs::remove_file(&entry.path)
+                    .map(|_| "Removed")
+                    .map_err(Into::into),
+                Err(err) => Err(err),
+            };
+            match restored {
+                Ok(action) => println!("{action} {path}"),
+                Err(err) => eprintln!("Failed to undo changes to {path}: {err}"),
+            }
+        }
+
+        if let Err(err) = self.journal.clear() {
+            eprintln!("Failed to remove {JOURNAL_DIR}: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::journal::record_in;
+
+    #[test]
+    fn undo() {
+        let root = std::env::temp_dir().join(format!("sql-parse-undo-{}", std::process::id()));
+        let dir = root.join("undo");
+        std::fs::create_dir_all(&root).unwrap();
+        let (changed, edited, latin1, created) = (
+            root.join("changed.py"),
+            root.join("edited.py"),
+            root.join("latin1.py"),
+            root.join("created.sql"),
+        );
+
+        let mut journal = None;
+        for (path, original, written) in [
+            (&changed, Some(&b"a = 1\n"[..]), &b"a = 2\n"[..]),
+            (&edited, Some(b"b = 1\n"), b"b = 2\n"),
+            (&latin1, Some(b"c = '\xe0'\n"), b"c = '\xe9'\n"),
+            (&created, None, b"SELECT 1\n"),
+        ] {
+            record_in(&dir, &mut journal, path, original, written).unwrap();
+            std::fs::write(path, written).unwrap();
+        }
+        // edited again after the run, the latin-1 edit only in bytes that aren't UTF-8
+        std::fs::write(&edited, "b = 3\n").unwrap();
+        std::fs::write(&latin1, b"c = '\xe8'\n").unwrap();
+
+        Undo {
+            journal: Journal::load_from(&dir).unwrap(),
+        }
+        .run();
+
+        let read = |path| std::fs::read_to_string(path).ok();
+        let contents = [read(&changed), read(&edited), read(&created)];
+        let latin1 = std::fs::read(&latin1).unwrap();
+        let cleared = !dir.exists();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            contents,
+            [
+                Some("a = 1\n".to_string()),
+                Some("b = 3\n".to_string()),
+                None
+            ]
+        );
+        assert_eq!(latin1, b"c = '\xe8'\n");
+        assert!(cleared);
+    }
+}
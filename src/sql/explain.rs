@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use super::lexer::{tokenize, TokenKind};
+use super::metrics::metrics;
+
+/// Statements `EXPLAIN QUERY PLAN` is run for.
+const EXPLAINED: &[&str] = &["SELECT", "INSERT", "UPDATE", "DELETE", "REPLACE"];
+
+/// Query plans from a local SQLite database, opened read-only.
+pub struct Explainer {
+    conn: rusqlite::Connection,
+}
+
+impl Explainer {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = rusqlite::Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// The plan of a single statement, with every parameter bound to `NULL`. `None` when the
+    /// statement isn't a query or can't be bound, e.g. when it's built with interpolation.
+    pub fn explain(&self, statement: &str) -> Option<Result<Vec<String>, rusqlite::Error>> {
+        let kind = metrics(statement).first()?.kind.clone();
+        if !EXPLAINED.contains(&kind.as_str()) {
+            return None;
+        }
+        let sql = to_sqlite(statement)?;
+        Some(self.query_plan(&sql))
+    }
+
+    fn query_plan(&self, sql: &str) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(&format!("EXPLAIN QUERY PLAN {sql}"))?;
+        for idx in 1..=stmt.parameter_count() {
+            stmt.raw_bind_parameter(idx, rusqlite::types::Null)?;
+        }
+        let mut rows = stmt.raw_query();
+        let mut details = Vec::new();
+        while let Some(row) = rows.next()? {
+            details.push(row.get::<_, String>(3)?);
+        }
+        Ok(details)
+    }
+}
+
+/// `sql` with `format`/`pyformat` placeholders swapped for ones SQLite understands. `None` when
+/// values are interpolated into it.
+fn to_sqlite(sql: &str) -> Option<String> {
+    let tokens = tokenize(sql);
+    let percent_style = tokens
+        .iter()
+        .any(|tok| tok.kind == TokenKind::Placeholder && tok.text.starts_with('%'));
+
+    let mut converted = String::with_capacity(sql.len());
+    for tok in &tokens {
+        match tok.kind {
+            TokenKind::Interpolation => return None,
+            TokenKind::Placeholder if tok.text.starts_with("%(") => {
+                converted.push(':');
+                converted.push_str(&tok.text[2..tok.text.len() - 2]);
+            }
+            TokenKind::Placeholder if tok.text.starts_with('%') => converted.push('?'),
+            // drivers using `%s` placeholders need literal `%` doubled
+            _ if percent_style => converted.push_str(&tok.text.replace("%%", "%")),
+            _ => converted.push_str(tok.text),
+        }
+    }
+    Some(converted)
+}
+
+/// Tables read in full according to a query plan.
+pub fn full_scans(plan: &[String]) -> Vec<&str> {
+    plan.iter()
+        .filter_map(|detail| {
+            let scanned = detail.strip_prefix("SCAN ")?;
+            let scanned = scanned.strip_prefix("TABLE ").unwrap_or(scanned);
+            let table = scanned.split(' ').next()?;
+            let is_table = !(scanned.contains(" USING ")
+                || table.starts_with('(')
+                || scanned.starts_with("CONSTANT ROW"));
+            is_table.then_some(table)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sqlite_placeholders() {
+        assert_eq!(
+            to_sqlite("SELECT '100%%' FROM t WHERE a = %s AND b = %(b)s").unwrap(),
+            "SELECT '100%' FROM t WHERE a = ? AND b = :b"
+        );
+        assert_eq!(
+            to_sqlite("SELECT '100%%' FROM t WHERE a = ?").unwrap(),
+            "SELECT '100%%' FROM t WHERE a = ?"
+        );
+        assert_eq!(to_sqlite("SELECT * FROM {table}"), None);
+    }
+
+    #[test]
+    fn scans() {
+        let plan = [
+            "SCAN users",
+            "SCAN TABLE orders",
+            "SCAN users USING COVERING INDEX idx",
+            "SEARCH users USING INTEGER PRIMARY KEY (rowid=?)",
+            "SCAN CONSTANT ROW",
+            "SCAN (subquery-1)",
+        ]
+        .map(String::from);
+        assert_eq!(full_scans(&plan), vec!["users", "orders"]);
+    }
+}
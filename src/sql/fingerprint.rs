@@ -0,0 +1,80 @@
+use super::lexer::{tokenize, TokenKind};
+
+/// `sql` with comments dropped, whitespace collapsed and words uppercased, so that formatting
+/// changes leave it alone.
+pub fn normalize(sql: &str) -> String {
+    normalize_tokens(sql, false)
+}
+
+/// `normalize` with literals, placeholders and interpolations replaced by `?`, leaving only the
+/// shape of the query.
+pub fn skeleton(sql: &str) -> String {
+    normalize_tokens(sql, true)
+}
+
+fn normalize_tokens(sql: &str, mask_values: bool) -> String {
+    tokenize(sql)
+        .into_iter()
+        .filter(|token| !token.is_trivia())
+        .map(|token| match token.kind {
+            TokenKind::Word => token.text.to_uppercase(),
+            TokenKind::String
+            | TokenKind::Number
+            | TokenKind::Placeholder
+            | TokenKind::Interpolation
+                if mask_values =>
+            {
+                "?".to_string()
+            }
+            _ => token.text.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Hash of the normalized SQL that stays the same across runs and platforms.
+pub fn fingerprint(sql: &str) -> String {
+    hash(normalize(sql))
+}
+
+/// 64-bit FNV-1a hash of `bytes` as hex.
+pub fn hash(bytes: impl AsRef<[u8]>) -> String {
+    let hash = bytes
+        .as_ref()
+        .iter()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn formatting_is_ignored() {
+        let a = "SELECT id, name\n    FROM users -- all of them\n    WHERE id = ?";
+        let b = "select id,name from USERS where id=?";
+        assert_eq!(normalize(b), "SELECT ID , NAME FROM USERS WHERE ID = ?");
+        assert_eq!(normalize(a), normalize(b));
+        assert_eq!(fingerprint(a), fingerprint(b));
+        assert_ne!(
+            fingerprint(a),
+            fingerprint("SELECT id FROM users WHERE id = 1")
+        );
+        assert_eq!(fingerprint("").len(), 16);
+    }
+
+    #[test]
+    fn values_are_masked() {
+        let a = "select * from users where id = 1 and name = 'bob' and org = %s";
+        let b = "SELECT * FROM users WHERE id = 42 AND name = {name} AND org = ?";
+        assert_eq!(
+            skeleton(a),
+            "SELECT * FROM USERS WHERE ID = ? AND NAME = ? AND ORG = ?"
+        );
+        assert_eq!(skeleton(a), skeleton(b));
+        assert_ne!(normalize(a), normalize(b));
+    }
+}
@@ -0,0 +1,541 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Whitespace,
+    Comment,
+    Word,
+    QuotedIdent,
+    String,
+    Number,
+    Placeholder,
+    Variable,
+    Interpolation,
+    Punct,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub range: Range<usize>,
+}
+
+impl<'a> Token<'a> {
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::Comment)
+    }
+
+    pub fn is_identifier(&self) -> bool {
+        match self.kind {
+            TokenKind::Word => !is_keyword(self.text),
+            TokenKind::QuotedIdent => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    pub fn is_punct(&self, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == punct
+    }
+
+    /// Identifier text without any surrounding `"`, `[]` or `` ` `` quoting.
+    pub fn unquoted(&self) -> &'a str {
+        if self.kind == TokenKind::QuotedIdent && self.text.len() >= 2 {
+            &self.text[1..self.text.len() - 1]
+        } else {
+            self.text
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "ADD",
+    "ALL",
+    "ALTER",
+    "AND",
+    "ANY",
+    "AS",
+    "ASC",
+    "BEGIN",
+    "BETWEEN",
+    "BIGINT",
+    "BIT",
+    "BLOB",
+    "BOOLEAN",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHAR",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "COMMIT",
+    "CONFLICT",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT_DATE",
+    "CURRENT_TIMESTAMP",
+    "DATE",
+    "DATETIME",
+    "DECIMAL",
+    "DECLARE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DO",
+    "DOUBLE",
+    "DROP",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCEPT",
+    "EXEC",
+    "EXECUTE",
+    "EXISTS",
+    "FALSE",
+    "FETCH",
+    "FIRST",
+    "FLOAT",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GO",
+    "GROUP",
+    "HAVING",
+    "IF",
+    "IGNORE",
+    "ILIKE",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INT",
+    "INTEGER",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LATERAL",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "MATCHED",
+    "MERGE",
+    "NATURAL",
+    "NEXT",
+    "NOLOCK",
+    "NOT",
+    "NOTHING",
+    "NULL",
+    "NUMERIC",
+    "NVARCHAR",
+    "OF",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OUTPUT",
+    "OVER",
+    "PARTITION",
+    "PRIMARY",
+    "REAL",
+    "REFERENCES",
+    "RENAME",
+    "REPLACE",
+    "RETURNING",
+    "RIGHT",
+    "ROLLBACK",
+    "ROW",
+    "ROWS",
+    "SELECT",
+    "SET",
+    "SMALLINT",
+    "TABLE",
+    "TEXT",
+    "THEN",
+    "TIME",
+    "TIMESTAMP",
+    "TO",
+    "TOP",
+    "TRANSACTION",
+    "TRUE",
+    "TRUNCATE",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USING",
+    "VALUES",
+    "VARCHAR",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WITH",
+];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .binary_search_by(|kw| cmp_ignore_ascii_case(kw, word))
+        .is_ok()
+}
+
+fn cmp_ignore_ascii_case(a: &str, b: &str) -> std::cmp::Ordering {
+    a.bytes()
+        .map(|b| b.to_ascii_uppercase())
+        .cmp(b.bytes().map(|b| b.to_ascii_uppercase()))
+}
+
+/// Split SQL text into tokens. Every byte of `sql` belongs to exactly one token so the token
+/// ranges can always be mapped back onto the source.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let mut lexer = Lexer {
+        sql,
+        bytes: sql.as_bytes(),
+        pos: 0,
+    };
+    let mut tokens = Vec::new();
+    while lexer.pos < sql.len() {
+        let start = lexer.pos;
+        let kind = lexer.next_kind();
+        tokens.push(Token {
+            kind,
+            text: &sql[start..lexer.pos],
+            range: start..lexer.pos,
+        });
+    }
+    tokens
+}
+
+struct Lexer<'a> {
+    sql: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    fn next_kind(&mut self) -> TokenKind {
+        let ch = self.bytes[self.pos];
+        match ch {
+            b if b.is_ascii_whitespace() => {
+                self.eat_while(|b| b.is_ascii_whitespace());
+                TokenKind::Whitespace
+            }
+            b'-' if self.peek(1) == Some(b'-') => {
+                self.eat_while(|b| b != b'\n');
+                TokenKind::Comment
+            }
+            b'/' if self.peek(1) == Some(b'*') => {
+                self.pos += 2;
+                self.eat_until("*/");
+                TokenKind::Comment
+            }
+            b'\'' => {
+                self.eat_quoted(b'\'', true);
+                TokenKind::String
+            }
+            b'N' | b'n' | b'E' | b'e' | b'X' | b'x' | b'B' | b'b'
+                if self.peek(1) == Some(b'\'') =>
+            {
+                self.pos += 1;
+                self.eat_quoted(b'\'', true);
+                TokenKind::String
+            }
+            b'"' => {
+                self.eat_quoted(b'"', false);
+                TokenKind::QuotedIdent
+            }
+            b'`' => {
+                self.eat_quoted(b'`', false);
+                TokenKind::QuotedIdent
+            }
+            b'[' => {
+                self.eat_quoted(b']', false);
+                TokenKind::QuotedIdent
+            }
+            b'{' if self.peek(1) == Some(b'{') => {
+                self.pos += 2;
+                TokenKind::Punct
+            }
+            b'{' => {
+                self.eat_interpolation();
+                TokenKind::Interpolation
+            }
+            b'}' if self.peek(1) == Some(b'}') => {
+                self.pos += 2;
+                TokenKind::Punct
+            }
+            b'?' => {
+                self.pos += 1;
+                TokenKind::Placeholder
+            }
+            b'%' => self.percent(),
+            b':' if self.peek(1) == Some(b':') => {
+                self.pos += 2;
+                TokenKind::Punct
+            }
+            b':' if self
+                .peek(1)
+                .is_some_and(|b| is_word_start(b) || b.is_ascii_digit()) =>
+            {
+                self.pos += 1;
+                self.eat_while(is_word_byte);
+                TokenKind::Placeholder
+            }
+            b'$' if self.peek(1).is_some_and(|b| b.is_ascii_digit()) => {
+                self.pos += 1;
+                self.eat_while(|b| b.is_ascii_digit());
+                TokenKind::Placeholder
+            }
+            b'@' => {
+                self.eat_while(|b| b == b'@');
+                self.eat_while(is_word_byte);
+                TokenKind::Variable
+            }
+            b if b.is_ascii_digit() => {
+                self.eat_number();
+                TokenKind::Number
+            }
+            b'.' if self.peek(1).is_some_and(|b| b.is_ascii_digit()) => {
+                self.eat_number();
+                TokenKind::Number
+            }
+            b if is_word_start(b) => {
+                self.eat_while(is_word_byte);
+                TokenKind::Word
+            }
+            _ => {
+                let len = self.sql[self.pos..]
+                    .chars()
+                    .next()
+                    .map_or(1, char::len_utf8);
+                self.pos += len;
+                TokenKind::Punct
+            }
+        }
+    }
+
+    fn eat_while(&mut self, pred: impl Fn(u8) -> bool) {
+        while self.pos < self.bytes.len() && pred(self.bytes[self.pos]) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat_until(&mut self, terminator: &str) {
+        match self.sql[self.pos..].find(terminator) {
+            Some(idx) => self.pos += idx + terminator.len(),
+            None => self.pos = self.bytes.len(),
+        }
+    }
+
+    /// Consume a quoted section starting at the opening delimiter. A doubled closing delimiter
+    /// is an escape, as is a backslash when `backslash_escapes` is set.
+    fn eat_quoted(&mut self, close: u8, backslash_escapes: bool) {
+        self.pos += 1;
+        while self.pos < self.bytes.len() {
+            let b = self.bytes[self.pos];
+            if backslash_escapes && b == b'\\' {
+                self.pos += 2;
+                continue;
+            }
+            self.pos += 1;
+            if b == close {
+                if self.peek(0) == Some(close) {
+                    self.pos += 1;
+                    continue;
+                }
+                return;
+            }
+        }
+        self.pos = self.bytes.len();
+    }
+
+    fn eat_interpolation(&mut self) {
+        let mut depth = 0;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return;
+                    }
+                }
+                _ => (),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn eat_number(&mut self) {
+        self.eat_while(|b| b.is_ascii_digit() || b == b'.');
+        if matches!(self.peek(0), Some(b'e' | b'E'))
+            && self
+                .peek(1)
+                .is_some_and(|b| b.is_ascii_digit() || b == b'-' || b == b'+')
+        {
+            self.pos += 2;
+            self.eat_while(|b| b.is_ascii_digit());
+        }
+    }
+
+    fn percent(&mut self) -> TokenKind {
+        match self.peek(1) {
+            Some(b'%') => {
+                self.pos += 2;
+                TokenKind::Punct
+            }
+            Some(b's') => {
+                self.pos += 2;
+                TokenKind::Placeholder
+            }
+            Some(b'(') => match self.sql[self.pos..].find(")s") {
+                Some(idx)
+                    if self.sql[self.pos + 2..self.pos + idx]
+                        .bytes()
+                        .all(is_word_byte) =>
+                {
+                    self.pos += idx + 2;
+                    TokenKind::Placeholder
+                }
+                _ => {
+                    self.pos += 1;
+                    TokenKind::Punct
+                }
+            },
+            _ => {
+                self.pos += 1;
+                TokenKind::Punct
+            }
+        }
+    }
+}
+
+fn is_word_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'#' || !b.is_ascii()
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b == b'#' || !b.is_ascii()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(sql)
+            .into_iter()
+            .filter(|tok| tok.kind != TokenKind::Whitespace)
+            .map(|tok| (tok.kind, tok.text))
+            .collect()
+    }
+
+    #[test]
+    fn keywords_sorted() {
+        let mut sorted = KEYWORDS.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(sorted, KEYWORDS);
+    }
+
+    #[test]
+    fn covers_every_byte() {
+        let sql = "SELECT é, \"x\" FROM [dbo].[t] -- hi\nWHERE a = 'it''s' /* c */";
+        let joined: String = tokenize(sql).iter().map(|tok| tok.text).collect();
+        assert_eq!(joined, sql);
+    }
+
+    #[test]
+    fn basic_select() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("SELECT a.b, 'x' FROM t WHERE id = 1.5"),
+            vec![
+                (Word, "SELECT"),
+                (Word, "a"),
+                (Punct, "."),
+                (Word, "b"),
+                (Punct, ","),
+                (String, "'x'"),
+                (Word, "FROM"),
+                (Word, "t"),
+                (Word, "WHERE"),
+                (Word, "id"),
+                (Punct, "="),
+                (Number, "1.5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_and_quotes() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("[my table] `x` \"y\"\"z\" -- DROP TABLE\n/* a */ N'b'"),
+            vec![
+                (QuotedIdent, "[my table]"),
+                (QuotedIdent, "`x`"),
+                (QuotedIdent, "\"y\"\"z\""),
+                (Comment, "-- DROP TABLE"),
+                (Comment, "/* a */"),
+                (String, "N'b'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn placeholders() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("? %s %(name)s :name :1 $1 @var x::int 5 % 2 %% %b"),
+            vec![
+                (Placeholder, "?"),
+                (Placeholder, "%s"),
+                (Placeholder, "%(name)s"),
+                (Placeholder, ":name"),
+                (Placeholder, ":1"),
+                (Placeholder, "$1"),
+                (Variable, "@var"),
+                (Word, "x"),
+                (Punct, "::"),
+                (Word, "int"),
+                (Number, "5"),
+                (Punct, "%"),
+                (Number, "2"),
+                (Punct, "%%"),
+                (Punct, "%"),
+                (Word, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn interpolations() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("{x} {d['k']} {{literal}}"),
+            vec![
+                (Interpolation, "{x}"),
+                (Interpolation, "{d['k']}"),
+                (Punct, "{{"),
+                (Word, "literal"),
+                (Punct, "}}"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use super::lexer::Token;
+use super::split::statement_tokens;
+
+const VERBS: &[&str] = &[
+    "SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "CREATE", "ALTER", "DROP", "TRUNCATE", "EXEC",
+    "EXECUTE", "CALL",
+];
+
+/// Size and shape of a single statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metrics {
+    /// Uppercased leading verb, looking past any `WITH` list.
+    pub kind: String,
+    pub tokens: usize,
+    pub joins: usize,
+    /// How deeply `SELECT`s are nested within parentheses.
+    pub subquery_depth: usize,
+}
+
+/// Metrics of each statement in `sql`.
+pub fn metrics(sql: &str) -> Vec<Metrics> {
+    statement_tokens(sql)
+        .iter()
+        .map(|tokens| Metrics {
+            kind: kind(tokens),
+            tokens: tokens.len(),
+            joins: tokens.iter().filter(|tok| tok.is_keyword("JOIN")).count(),
+            subquery_depth: subquery_depth(tokens),
+        })
+        .collect()
+}
+
+fn kind(tokens: &[Token]) -> String {
+    let mut depth = 0;
+    for tok in tokens {
+        if tok.is_punct("(") {
+            depth += 1;
+        } else if tok.is_punct(")") {
+            depth -= 1;
+        } else if depth == 0 && VERBS.iter().any(|verb| tok.is_keyword(verb)) {
+            return tok.text.to_uppercase();
+        }
+    }
+    tokens[0].text.to_uppercase()
+}
+
+fn subquery_depth(tokens: &[Token]) -> usize {
+    // whether each open parenthesis holds a subquery
+    let mut parens = Vec::new();
+    let mut max_depth = 0;
+    for (idx, tok) in tokens.iter().enumerate() {
+        if tok.is_punct("(") {
+            let is_subquery = tokens
+                .get(idx + 1)
+                .is_some_and(|next| next.is_keyword("SELECT") || next.is_keyword("WITH"));
+            parens.push(is_subquery);
+            let depth = parens.iter().filter(|&&is_subquery| is_subquery).count();
+            max_depth = max_depth.max(depth);
+        } else if tok.is_punct(")") {
+            parens.pop();
+        }
+    }
+    max_depth
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn statements() {
+        let sql = "WITH recent AS (SELECT * FROM orders) \
+                   INSERT INTO archive SELECT * FROM recent r JOIN users u ON u.id = r.user_id \
+                   WHERE u.id IN (SELECT id FROM vip WHERE org IN (SELECT id FROM orgs)); \
+                   select 1; \
+                   VACUUM";
+        let summary = metrics(sql)
+            .into_iter()
+            .map(|m| (m.kind, m.joins, m.subquery_depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("INSERT".to_string(), 1, 2),
+                ("SELECT".to_string(), 0, 0),
+                ("VACUUM".to_string(), 0, 0),
+            ]
+        );
+    }
+}
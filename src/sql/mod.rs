@@ -0,0 +1,18 @@
+mod explain;
+mod fingerprint;
+pub mod lexer;
+mod metrics;
+mod params;
+mod refs;
+mod rename;
+mod schema;
+mod split;
+
+pub use explain::{full_scans, Explainer};
+pub use fingerprint::{fingerprint, hash, skeleton};
+pub use metrics::metrics;
+pub use params::{placeholders, value_slot, ParamStyle, Placeholder, Slot};
+pub use refs::{references, Access, RefKind, Reference};
+pub use rename::{rename_edits, Rename};
+pub use schema::Schema;
+pub use split::{split_statements, statement_tokens};
@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::lexer::{tokenize, Token, TokenKind};
+
+/// DB-API 2.0 placeholder styles, plus `$1` for `numeric`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamStyle {
+    /// `WHERE id = ?`
+    Qmark,
+    /// `WHERE id = :1` or `WHERE id = $1`
+    Numeric,
+    /// `WHERE id = :id`
+    Named,
+    /// `WHERE id = %s`
+    Format,
+    /// `WHERE id = %(id)s`
+    Pyformat,
+}
+
+impl std::fmt::Display for ParamStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ParamStyle::Qmark => "qmark",
+            ParamStyle::Numeric => "numeric",
+            ParamStyle::Named => "named",
+            ParamStyle::Format => "format",
+            ParamStyle::Pyformat => "pyformat",
+        };
+        f.pad(name)
+    }
+}
+
+/// A bound parameter marker within a SQL string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder<'a> {
+    pub style: ParamStyle,
+    /// The name of `named`/`pyformat` placeholders or the index of `numeric` ones.
+    pub key: Option<&'a str>,
+    pub range: Range<usize>,
+}
+
+pub fn placeholders(sql: &str) -> Vec<Placeholder<'_>> {
+    tokenize(sql)
+        .into_iter()
+        .filter(|token| token.kind == TokenKind::Placeholder)
+        .map(|token| {
+            let text = token.text;
+            let (style, key) = if text == "?" {
+                (ParamStyle::Qmark, None)
+            } else if let Some(name) = text.strip_prefix("%(") {
+                (ParamStyle::Pyformat, name.strip_suffix(")s"))
+            } else if text.starts_with('%') {
+                (ParamStyle::Format, None)
+            } else if let Some(index) = text.strip_prefix('$') {
+                (ParamStyle::Numeric, Some(index))
+            } else {
+                let name = &text[1..];
+                if name.bytes().all(|b| b.is_ascii_digit()) {
+                    (ParamStyle::Numeric, Some(name))
+                } else {
+                    (ParamStyle::Named, Some(name))
+                }
+            };
+            Placeholder {
+                style,
+                key,
+                range: token.range,
+            }
+        })
+        .collect()
+}
+
+/// Where a value spliced into the SQL text ends up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Slot {
+    /// Compared with, inserted or used as a limit, so it can be bound instead. The range to
+    /// replace with a placeholder, including any quotes around the value.
+    Value(Range<usize>),
+    /// Anything else, most likely a table or column name.
+    Identifier,
+    /// Part of a SQL string literal or comment.
+    Literal,
+}
+
+/// Where the value spliced into `sql` at `range`, e.g. an f-string field, ends up.
+pub fn value_slot(sql: &str, range: Range<usize>) -> Slot {
+    let tokens = tokenize(sql);
+    let Some(idx) = tokens
+        .iter()
+        .position(|tok| tok.range.contains(&range.start))
+    else {
+        return Slot::Identifier;
+    };
+
+    let token = &tokens[idx];
+    let quoted = range.start > 0 && token.range == (range.start - 1..range.end + 1);
+    match token.kind {
+        TokenKind::String if quoted && token.text.starts_with('\'') => {}
+        TokenKind::String | TokenKind::Comment => return Slot::Literal,
+        _ if token.range != range => return Slot::Identifier,
+        _ => {}
+    }
+
+    let before = tokens[..idx]
+        .iter()
+        .filter(|tok| !tok.is_trivia())
+        .collect::<Vec<_>>();
+    let qualifies = tokens[idx + 1..]
+        .iter()
+        .find(|tok| !tok.is_trivia())
+        .is_some_and(|tok| tok.is_punct("."));
+    let is_value = match before.last() {
+        _ if qualifies => false,
+        Some(prev) if ["=", "<", ">"].iter().any(|op| prev.is_punct(op)) => true,
+        Some(prev)
+            if ["LIKE", "ILIKE", "LIMIT", "OFFSET", "BETWEEN"]
+                .iter()
+                .any(|kw| prev.is_keyword(kw)) =>
+        {
+            true
+        }
+        Some(prev) if prev.is_keyword("AND") => {
+            // `BETWEEN {low} AND {high}`
+            before.len() >= 3 && before[before.len() - 3].is_keyword("BETWEEN")
+        }
+        Some(prev) if prev.is_punct("(") || prev.is_punct(",") => in_value_list(&before),
+        _ => false,
+    };
+    if is_value {
+        Slot::Value(token.range.clone())
+    } else {
+        Slot::Identifier
+    }
+}
+
+/// Whether the parentheses around the end of `before` hold `VALUES` or `IN` lists.
+fn in_value_list(before: &[&Token]) -> bool {
+    let mut depth = 0;
+    for (idx, tok) in before.iter().enumerate().rev() {
+        if tok.is_punct(")") {
+            depth += 1;
+        } else if tok.is_punct("(") && depth > 0 {
+            depth -= 1;
+        } else if tok.is_punct("(") {
+            return idx > 0
+                && (before[idx - 1].is_keyword("VALUES") || before[idx - 1].is_keyword("IN"));
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classify() {
+        let sql = "SELECT '?', %% FROM t WHERE a = ? AND b = %s AND c = %(c)s AND d = :d AND e = :2 AND f = $3 -- :x";
+        let found = placeholders(sql)
+            .into_iter()
+            .map(|p| (p.style, p.key, &sql[p.range]))
+            .collect::<Vec<_>>();
+
+        use ParamStyle::*;
+        assert_eq!(
+            found,
+            vec![
+                (Qmark, None, "?"),
+                (Format, None, "%s"),
+                (Pyformat, Some("c"), "%(c)s"),
+                (Named, Some("d"), ":d"),
+                (Numeric, Some("2"), ":2"),
+                (Numeric, Some("3"), "$3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn slots() {
+        let nth_slot = |sql: &str, field: &str, nth: usize| {
+            let (start, _) = sql.match_indices(field).nth(nth).unwrap();
+            value_slot(sql, start..start + field.len())
+        };
+        let slot = |sql: &str, field: &str| nth_slot(sql, field, 0);
+        let sql = "SELECT {col} FROM {table} WHERE id = {id} AND name LIKE '{name}' AND {t}.x = 1";
+        assert_eq!(slot(sql, "{col}"), Slot::Identifier);
+        assert_eq!(slot(sql, "{table}"), Slot::Identifier);
+        assert_eq!(slot(sql, "{id}"), Slot::Value(37..41));
+        assert_eq!(slot(sql, "{name}"), Slot::Value(56..64));
+        assert_eq!(slot(sql, "{t}"), Slot::Identifier);
+
+        let sql = "INSERT INTO t (a, b) VALUES (%s, 'x%s') -- %s";
+        assert_eq!(slot(sql, "%s"), Slot::Value(29..31));
+        assert_eq!(nth_slot(sql, "%s", 1), Slot::Literal);
+        assert_eq!(nth_slot(sql, "%s", 2), Slot::Literal);
+
+        let sql = "SELECT * FROM t WHERE a IN ({a}, {b}) AND c BETWEEN {lo} AND {hi} LIMIT {n}";
+        for field in ["{a}", "{b}", "{lo}", "{hi}", "{n}"] {
+            assert!(matches!(slot(sql, field), Slot::Value(_)), "{field}");
+        }
+    }
+}
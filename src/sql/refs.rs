@@ -0,0 +1,642 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use serde::Serialize;
+
+use super::lexer::{Token, TokenKind};
+use super::split::statement_tokens;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    Read,
+    Write,
+    Ddl,
+}
+
+impl std::fmt::Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let access = match self {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::Ddl => "ddl",
+        };
+        f.pad(access)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    Table,
+    Column,
+}
+
+/// A table or column named by a SQL statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub kind: RefKind,
+    /// Owning table of a column, if it could be resolved. Always set for tables.
+    pub table: Option<String>,
+    pub name: String,
+    pub access: Access,
+    /// Byte range of the identifier token(s) within the SQL text.
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verb {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Merge,
+    Ddl,
+    Other,
+}
+
+impl Verb {
+    fn from_token(token: &Token) -> Option<Self> {
+        let verb = match token.text.to_ascii_uppercase().as_str() {
+            "SELECT" => Self::Select,
+            "INSERT" | "REPLACE" => Self::Insert,
+            "UPDATE" => Self::Update,
+            "DELETE" => Self::Delete,
+            "MERGE" => Self::Merge,
+            "CREATE" | "ALTER" | "DROP" | "TRUNCATE" => Self::Ddl,
+            _ => return None,
+        };
+        (token.kind == TokenKind::Word).then_some(verb)
+    }
+}
+
+/// Find every table and column referenced by `sql`, one statement at a time.
+pub fn references(sql: &str) -> Vec<Reference> {
+    statement_tokens(sql)
+        .iter()
+        .flat_map(|stmt| Statement::new(stmt).references())
+        .collect()
+}
+
+struct Statement<'s, 'a> {
+    tokens: &'s [Token<'a>],
+    verb: Verb,
+    consumed: HashSet<usize>,
+    aliases: HashMap<String, String>,
+    ctes: HashSet<String>,
+    /// Token ranges of the CTE bodies, each a scope of its own for unqualified columns.
+    cte_bodies: Vec<Range<usize>>,
+    /// Tables and CTEs read from or written to, by scope.
+    sources: Vec<Source>,
+    /// Names given to select-list expressions, which ORDER BY, GROUP BY and HAVING can refer to.
+    column_aliases: HashSet<String>,
+    /// Token ranges of INSERT column lists and the table they belong to.
+    write_columns: Vec<(Range<usize>, Option<String>)>,
+    refs: Vec<Reference>,
+}
+
+/// A table or CTE named in a statement, and the CTE body it's named in, if any.
+struct Source {
+    scope: Option<usize>,
+    name: String,
+    is_cte: bool,
+}
+
+impl<'s, 'a> Statement<'s, 'a> {
+    fn new(tokens: &'s [Token<'a>]) -> Self {
+        let mut depth = 0;
+        let mut verb = Verb::Other;
+        for tok in tokens {
+            if tok.is_punct("(") {
+                depth += 1;
+            } else if tok.is_punct(")") {
+                depth -= 1;
+            } else if depth == 0 {
+                if let Some(v) = Verb::from_token(tok) {
+                    verb = v;
+                    break;
+                }
+            }
+        }
+
+        Self {
+            tokens,
+            verb,
+            consumed: HashSet::new(),
+            aliases: HashMap::new(),
+            ctes: HashSet::new(),
+            cte_bodies: Vec::new(),
+            sources: Vec::new(),
+            column_aliases: HashSet::new(),
+            write_columns: Vec::new(),
+            refs: Vec::new(),
+        }
+    }
+
+    fn references(mut self) -> Vec<Reference> {
+        self.find_ctes();
+        self.find_tables();
+        self.find_columns();
+        self.refs.sort_by_key(|r| r.range.start);
+        self.refs
+    }
+
+    fn tok(&self, idx: usize) -> Option<&Token<'a>> {
+        self.tokens.get(idx)
+    }
+
+    fn prev(&self, idx: usize) -> Option<&Token<'a>> {
+        idx.checked_sub(1).and_then(|idx| self.tokens.get(idx))
+    }
+
+    fn find_ctes(&mut self) {
+        for idx in 0..self.tokens.len() {
+            let is_cte = self.tokens[idx].is_identifier()
+                && self.prev(idx).is_some_and(|tok| {
+                    tok.is_keyword("WITH") || tok.is_keyword("RECURSIVE") || tok.is_punct(",")
+                })
+                && self.tok(idx + 1).is_some_and(|tok| tok.is_keyword("AS"))
+                && self.tok(idx + 2).is_some_and(|tok| tok.is_punct("("));
+            if is_cte {
+                self.ctes.insert(self.tokens[idx].unquoted().to_lowercase());
+                self.consumed.insert(idx);
+                if let Some(close) = self.matching_paren(idx + 2) {
+                    self.cte_bodies.push(idx + 2..close + 1);
+                }
+            }
+        }
+    }
+
+    fn find_tables(&mut self) {
+        let mut delete_target_seen = false;
+        let mut idx = 0;
+        while idx < self.tokens.len() {
+            let tok = &self.tokens[idx];
+            let access = if tok.is_keyword("FROM") {
+                if self.verb == Verb::Delete && !delete_target_seen {
+                    delete_target_seen = true;
+                    Some(Access::Write)
+                } else {
+                    Some(Access::Read)
+                }
+            } else if tok.is_keyword("JOIN") || tok.is_keyword("USING") {
+                Some(Access::Read)
+            } else if tok.is_keyword("INTO")
+                || (tok.is_keyword("UPDATE") && self.starts_main_clause(idx))
+            {
+                Some(Access::Write)
+            } else if tok.is_keyword("TABLE")
+                || tok.is_keyword("REFERENCES")
+                || (tok.is_keyword("TRUNCATE") && self.verb == Verb::Ddl)
+                || (tok.is_keyword("ON") && self.is_create_index())
+            {
+                (self.verb == Verb::Ddl).then_some(Access::Ddl)
+            } else {
+                None
+            };
+
+            let Some(access) = access else {
+                idx += 1;
+                continue;
+            };
+
+            idx += 1;
+            idx = self.skip_if_exists(idx);
+            let first_source = self.sources.len();
+            while let Some(next) = self.table_name(idx, access) {
+                idx = next;
+                let is_list = tok.is_keyword("FROM") || tok.is_keyword("USING");
+                if is_list && self.tok(idx).is_some_and(|t| t.is_punct(",")) {
+                    idx += 1;
+                } else {
+                    break;
+                }
+            }
+
+            if tok.is_keyword("INTO") {
+                let target = self.sources.get(first_source).map(|s| s.name.clone());
+                self.mark_insert_columns(idx, target);
+            }
+        }
+
+        if self.verb == Verb::Merge {
+            if let Some(idx) = self.tokens.iter().position(|t| t.is_keyword("INSERT")) {
+                let target = self
+                    .refs
+                    .iter()
+                    .find(|r| r.kind == RefKind::Table && r.access == Access::Write)
+                    .map(|r| r.name.clone());
+                self.mark_insert_columns(idx + 1, target);
+            }
+        }
+    }
+
+    /// Whether `idx` is the first token of the statement or directly follows a CTE list.
+    fn starts_main_clause(&self, idx: usize) -> bool {
+        match self.prev(idx) {
+            Some(prev) => prev.is_punct(")"),
+            None => true,
+        }
+    }
+
+    fn is_create_index(&self) -> bool {
+        self.tokens.first().is_some_and(|t| t.is_keyword("CREATE"))
+            && self.tokens.iter().take(4).any(|t| t.is_keyword("INDEX"))
+    }
+
+    fn skip_if_exists(&self, mut idx: usize) -> usize {
+        for kw in ["IF", "NOT", "EXISTS"] {
+            if self.tok(idx).is_some_and(|t| t.is_keyword(kw)) {
+                idx += 1;
+            }
+        }
+        idx
+    }
+
+    /// Parse a possibly qualified table name and its alias starting at `idx`. Returns the index
+    /// following the table expression.
+    fn table_name(&mut self, idx: usize, access: Access) -> Option<usize> {
+        let (parts, range, mut next) = self.dotted_name(idx)?;
+        let name = parts.join(".");
+        let is_cte = self.ctes.contains(&name.to_lowercase());
+        self.sources.push(Source {
+            scope: self.scope(idx),
+            name: name.clone(),
+            is_cte,
+        });
+
+        if !is_cte {
+            self.refs.push(Reference {
+                kind: RefKind::Table,
+                table: Some(name.clone()),
+                name: name.clone(),
+                access,
+                range,
+            });
+        }
+
+        if self.tok(next).is_some_and(|t| t.is_keyword("AS")) {
+            next += 1;
+        }
+        if let Some(alias) = self.tok(next).filter(|t| t.is_identifier()) {
+            self.aliases
+                .insert(alias.unquoted().to_lowercase(), name.clone());
+            self.consumed.insert(next);
+            next += 1;
+        }
+        self.aliases
+            .entry(parts.last().unwrap().to_lowercase())
+            .or_insert(name);
+
+        Some(next)
+    }
+
+    /// An identifier optionally qualified by `.` separated parts.
+    fn dotted_name(&mut self, idx: usize) -> Option<(Vec<String>, Range<usize>, usize)> {
+        let first = self.tok(idx).filter(|t| t.is_identifier())?;
+        let start = first.range.start;
+        let mut end = first.range.end;
+        let mut parts = vec![first.unquoted().to_string()];
+        self.consumed.insert(idx);
+
+        let mut next = idx + 1;
+        while self.tok(next).is_some_and(|t| t.is_punct(".")) {
+            match self.tok(next + 1) {
+                Some(part) if matches!(part.kind, TokenKind::Word | TokenKind::QuotedIdent) => {
+                    parts.push(part.unquoted().to_string());
+                    end = part.range.end;
+                    self.consumed.insert(next + 1);
+                    next += 2;
+                }
+                _ => break,
+            }
+        }
+        Some((parts, start..end, next))
+    }
+
+    /// The CTE body `idx` is in, `None` for the main query.
+    fn scope(&self, idx: usize) -> Option<usize> {
+        self.cte_bodies.iter().rposition(|body| body.contains(&idx))
+    }
+
+    fn mark_insert_columns(&mut self, idx: usize, table: Option<String>) {
+        if !self.tok(idx).is_some_and(|t| t.is_punct("(")) {
+            return;
+        }
+        let is_subquery = self
+            .tok(idx + 1)
+            .is_some_and(|t| t.is_keyword("SELECT") || t.is_keyword("WITH"));
+        if is_subquery {
+            return;
+        }
+        if let Some(close) = self.matching_paren(idx) {
+            self.write_columns.push((idx..close, table));
+        }
+    }
+
+    fn matching_paren(&self, open: usize) -> Option<usize> {
+        let mut depth = 0;
+        for (idx, tok) in self.tokens.iter().enumerate().skip(open) {
+            if tok.is_punct("(") {
+                depth += 1;
+            } else if tok.is_punct(")") {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+        }
+        None
+    }
+
+    /// The only table unqualified columns of `scope` can belong to. `None` when there are several,
+    /// or the only one is a CTE.
+    fn sole_table(&self, scope: Option<usize>) -> Option<String> {
+        let mut sources = self.sources.iter().filter(|s| s.scope == scope);
+        let first = sources.next()?;
+        let is_sole = sources.all(|s| s.name.eq_ignore_ascii_case(&first.name));
+        (is_sole && !first.is_cte).then(|| first.name.clone())
+    }
+
+    /// Collect `expr AS name` and `expr name` aliases. Table aliases and CTE names are consumed
+    /// by then.
+    fn find_column_aliases(&mut self) {
+        for idx in 0..self.tokens.len() {
+            let tok = &self.tokens[idx];
+            if tok.is_identifier() && !self.consumed.contains(&idx) && self.is_alias(idx) {
+                self.column_aliases.insert(tok.unquoted().to_lowercase());
+            }
+        }
+    }
+
+    fn find_columns(&mut self) {
+        self.find_column_aliases();
+        let mut in_set_clause = false;
+        // ORDER BY, GROUP BY and HAVING, where select-list aliases are in scope
+        let mut in_alias_clause = false;
+        let mut depth = 0;
+        let mut idx = 0;
+
+        while idx < self.tokens.len() {
+            let tok = &self.tokens[idx];
+            if tok.is_punct("(") {
+                depth += 1;
+            } else if tok.is_punct(")") {
+                depth -= 1;
+            } else if tok.is_keyword("SET") {
+                in_set_clause = true;
+            } else if ["WHERE", "FROM", "OUTPUT", "RETURNING", "WHEN"]
+                .iter()
+                .any(|kw| tok.is_keyword(kw))
+            {
+                in_set_clause = false;
+            }
+            if ["ORDER", "GROUP", "HAVING"]
+                .iter()
+                .any(|kw| tok.is_keyword(kw))
+            {
+                in_alias_clause = true;
+            } else if [
+                "SELECT", "FROM", "WHERE", "LIMIT", "OFFSET", "UNION", "EXCEPT",
+            ]
+            .iter()
+            .any(|kw| tok.is_keyword(kw))
+            {
+                in_alias_clause = false;
+            }
+
+            if self.consumed.contains(&idx) || !self.is_column_candidate(idx) {
+                idx += 1;
+                continue;
+            }
+
+            let Some((mut parts, range, next)) = self.dotted_name(idx) else {
+                idx += 1;
+                continue;
+            };
+            if self.tok(next).is_some_and(|t| t.is_punct("(")) {
+                idx = next;
+                continue;
+            }
+            // `alias.*` isn't a column
+            if self.tok(next).is_some_and(|t| t.is_punct(".")) {
+                idx = next + 1;
+                continue;
+            }
+
+            if in_alias_clause
+                && parts.len() == 1
+                && self.column_aliases.contains(&parts[0].to_lowercase())
+            {
+                idx = next;
+                continue;
+            }
+
+            let name = parts.pop().unwrap();
+            let insert_table = self
+                .write_columns
+                .iter()
+                .find(|(cols, _)| cols.contains(&idx))
+                .map(|(_, table)| table.clone());
+            let table = if parts.is_empty() {
+                match &insert_table {
+                    Some(table) => table.clone(),
+                    None => self.sole_table(self.scope(idx)),
+                }
+            } else {
+                let qualifier = parts.join(".");
+                Some(
+                    self.aliases
+                        .get(&qualifier.to_lowercase())
+                        .cloned()
+                        .unwrap_or(qualifier),
+                )
+            };
+
+            let access = if self.verb == Verb::Ddl {
+                Access::Ddl
+            } else if insert_table.is_some()
+                || (in_set_clause && depth == 0 && self.tok(next).is_some_and(|t| t.is_punct("=")))
+            {
+                Access::Write
+            } else {
+                Access::Read
+            };
+
+            self.refs.push(Reference {
+                kind: RefKind::Column,
+                table,
+                name,
+                access,
+                range,
+            });
+            idx = next;
+        }
+    }
+
+    fn is_column_candidate(&self, idx: usize) -> bool {
+        let tok = &self.tokens[idx];
+        if !tok.is_identifier() {
+            return false;
+        }
+        if self.ctes.contains(&tok.unquoted().to_lowercase())
+            && !self.tok(idx + 1).is_some_and(|t| t.is_punct("."))
+        {
+            return false;
+        }
+
+        match self.prev(idx) {
+            Some(prev) if prev.is_punct("::") || prev.is_punct(".") => false,
+            _ => !self.is_alias(idx),
+        }
+    }
+
+    /// Whether the identifier at `idx` names the expression before it, or is a type name.
+    fn is_alias(&self, idx: usize) -> bool {
+        match self.prev(idx) {
+            Some(prev) if prev.is_keyword("AS") => true,
+            // an identifier directly following an expression is an implicit alias or a type name
+            Some(prev) => {
+                prev.is_identifier()
+                    || prev.is_punct(")")
+                    || matches!(
+                        prev.kind,
+                        TokenKind::Number
+                            | TokenKind::String
+                            | TokenKind::Placeholder
+                            | TokenKind::Interpolation
+                            | TokenKind::Variable
+                    )
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn refs(sql: &str) -> Vec<String> {
+        references(sql)
+            .into_iter()
+            .map(|r| {
+                let table = r.table.unwrap_or_else(|| "?".into());
+                match r.kind {
+                    RefKind::Table => format!("table {} {}", r.name, r.access),
+                    RefKind::Column => format!("column {}.{} {}", table, r.name, r.access),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn simple_select() {
+        assert_eq!(
+            refs("SELECT id, name FROM users WHERE active = 1"),
+            vec![
+                "column users.id read",
+                "column users.name read",
+                "table users read",
+                "column users.active read",
+            ]
+        );
+    }
+
+    #[test]
+    fn join_with_aliases() {
+        assert_eq!(
+            refs("SELECT u.name AS n, o.total FROM users u JOIN orders AS o ON o.user_id = u.id"),
+            vec![
+                "column users.name read",
+                "column orders.total read",
+                "table users read",
+                "table orders read",
+                "column orders.user_id read",
+                "column users.id read",
+            ]
+        );
+    }
+
+    #[test]
+    fn insert_columns_are_writes() {
+        assert_eq!(
+            refs("INSERT INTO [dbo].[users] (id, name) SELECT id, name FROM staging"),
+            vec![
+                "table dbo.users write",
+                "column dbo.users.id write",
+                "column dbo.users.name write",
+                "column ?.id read",
+                "column ?.name read",
+                "table staging read",
+            ]
+        );
+    }
+
+    #[test]
+    fn update_set() {
+        assert_eq!(
+            refs("UPDATE users SET name = %s, seen = now() WHERE id = %s"),
+            vec![
+                "table users write",
+                "column users.name write",
+                "column users.seen write",
+                "column users.id read",
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_from() {
+        assert_eq!(
+            refs("DELETE FROM sessions WHERE expires < {now}"),
+            vec!["table sessions write", "column sessions.expires read",]
+        );
+    }
+
+    #[test]
+    fn create_table() {
+        assert_eq!(
+            refs("CREATE TABLE IF NOT EXISTS t (id INTEGER PRIMARY KEY, body my_type NOT NULL)"),
+            vec!["table t ddl", "column t.id ddl", "column t.body ddl",]
+        );
+    }
+
+    #[test]
+    fn ctes_are_not_tables() {
+        assert_eq!(
+            refs("WITH recent AS (SELECT id FROM events) SELECT id FROM recent"),
+            vec![
+                "column events.id read",
+                "table events read",
+                "column ?.id read",
+            ]
+        );
+    }
+
+    #[test]
+    fn select_aliases_are_not_columns() {
+        assert_eq!(
+            refs(
+                "SELECT COUNT(*) AS cnt, name n FROM users GROUP BY n HAVING cnt > 1 ORDER BY cnt"
+            ),
+            vec!["column users.name read", "table users read"]
+        );
+        assert_eq!(
+            refs("SELECT id AS cnt FROM users WHERE cnt = 1"),
+            vec![
+                "column users.id read",
+                "table users read",
+                "column users.cnt read"
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_statements() {
+        assert_eq!(
+            refs("DROP TABLE a; SELECT x FROM b"),
+            vec!["table a ddl", "column b.x read", "table b read",]
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use std::ops::Range;
+
+use anyhow::bail;
+
+use super::lexer::{tokenize, Token, TokenKind};
+use super::refs::{references, RefKind};
+
+/// A table or column to rename, as given to `--table old=new` or `--column [table.]old=new`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rename {
+    Table {
+        old: String,
+        new: String,
+    },
+    Column {
+        table: Option<String>,
+        old: String,
+        new: String,
+    },
+}
+
+impl Rename {
+    pub fn table(arg: &str) -> anyhow::Result<Self> {
+        let (old, new) = split_arg(arg)?;
+        Ok(Self::Table {
+            old: old.to_string(),
+            new: new.to_string(),
+        })
+    }
+
+    pub fn column(arg: &str) -> anyhow::Result<Self> {
+        let (old, new) = split_arg(arg)?;
+        let (table, old) = match old.rsplit_once('.') {
+            Some((table, old)) => (Some(table.to_string()), old),
+            None => (None, old),
+        };
+        Ok(Self::Column {
+            table,
+            old: old.to_string(),
+            new: new.to_string(),
+        })
+    }
+}
+
+fn split_arg(arg: &str) -> anyhow::Result<(&str, &str)> {
+    match arg.split_once('=') {
+        Some((old, new)) if !old.trim().is_empty() && !new.trim().is_empty() => {
+            if new.contains('.') {
+                bail!("new name `{new}` can't be qualified");
+            }
+            Ok((old.trim(), new.trim()))
+        }
+        _ => bail!("expected OLD=NEW, got `{arg}`"),
+    }
+}
+
+/// Edits renaming identifiers in a statement along with the references left alone.
+#[derive(Debug, Default)]
+pub struct RenameEdits {
+    pub edits: Vec<(Range<usize>, String)>,
+    /// Columns named like a renamed column that can't be tied to a table, with why.
+    pub skipped: Vec<(Range<usize>, String)>,
+}
+
+/// Edits to `sql` renaming the identifiers given by `renames`. Only identifier tokens naming
+/// the table or column are touched, leaving aliases, string literals and comments alone.
+/// Ranges are relative to `sql` and sorted.
+pub fn rename_edits(sql: &str, renames: &[Rename]) -> RenameEdits {
+    let tokens = tokenize(sql)
+        .into_iter()
+        .filter(|tok| tok.is_identifier())
+        .collect::<Vec<_>>();
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut push = |token: &Token, new: &str| {
+        if !edits.iter().any(|(range, _)| *range == token.range) {
+            edits.push((token.range.clone(), quote_like(token, new)));
+        }
+    };
+
+    let mut skipped = Vec::new();
+    for reference in references(sql) {
+        let parts = tokens
+            .iter()
+            .filter(|tok| {
+                reference.range.start <= tok.range.start && tok.range.end <= reference.range.end
+            })
+            .collect::<Vec<_>>();
+        let Some((name, qualifier)) = parts.split_last() else {
+            continue;
+        };
+
+        for rename in renames {
+            match (rename, reference.kind) {
+                (Rename::Table { old, new }, RefKind::Table) => {
+                    if names_match(&parts, old) {
+                        push(name, new);
+                    }
+                }
+                // `users.id` where `users` isn't an alias of some other table
+                (Rename::Table { old, new }, RefKind::Column) => {
+                    let resolved = reference
+                        .table
+                        .as_deref()
+                        .is_some_and(|table| last_part(table).eq_ignore_ascii_case(last_part(old)));
+                    if resolved && names_match(qualifier, old) {
+                        push(qualifier.last().unwrap(), new);
+                    }
+                }
+                (Rename::Column { table, old, new }, RefKind::Column) => {
+                    let owned = match table {
+                        Some(table) => reference.table.as_deref().is_some_and(|owner| {
+                            last_part(owner).eq_ignore_ascii_case(last_part(table))
+                        }),
+                        None => true,
+                    };
+                    if !ident_eq(name, old) {
+                        continue;
+                    }
+                    if owned {
+                        push(name, new);
+                    } else if let (Some(table), None) = (table, &reference.table) {
+                        skipped.push((
+                            name.range.clone(),
+                            format!(
+                                "can't tell whether `{}` is a column of `{table}`",
+                                name.text
+                            ),
+                        ));
+                    }
+                }
+                (Rename::Column { .. }, RefKind::Table) => {}
+            }
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    skipped.dedup();
+    RenameEdits { edits, skipped }
+}
+
+fn last_part(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// Whether the dotted name made of `parts` refers to `name`. An unqualified `name` matches
+/// regardless of schema.
+fn names_match(parts: &[&Token], name: &str) -> bool {
+    let expected = name.split('.').collect::<Vec<_>>();
+    if parts.len() < expected.len() || (expected.len() > 1 && parts.len() != expected.len()) {
+        return false;
+    }
+    parts
+        .iter()
+        .rev()
+        .zip(expected.iter().rev())
+        .all(|(tok, part)| ident_eq(tok, part))
+}
+
+/// Unquoted identifiers are case insensitive while quoted ones have to match exactly.
+fn ident_eq(token: &Token, name: &str) -> bool {
+    match token.kind {
+        TokenKind::QuotedIdent => {
+            let close = &token.text[token.text.len() - 1..];
+            token.unquoted().replace(&close.repeat(2), close) == name
+        }
+        _ => token.text.eq_ignore_ascii_case(name),
+    }
+}
+
+/// `new` quoted the same way as `token`, adding quotes when it isn't a plain identifier.
+fn quote_like(token: &Token, new: &str) -> String {
+    let (open, close) = match token.kind {
+        TokenKind::QuotedIdent => (&token.text[..1], &token.text[token.text.len() - 1..]),
+        _ => {
+            let tokens = tokenize(new);
+            if matches!(tokens.as_slice(), [tok] if tok.kind == TokenKind::Word && tok.is_identifier())
+            {
+                return new.to_string();
+            }
+            ("\"", "\"")
+        }
+    };
+    format!("{open}{}{close}", new.replace(close, &close.repeat(2)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rename(sql: &str, renames: &[Rename]) -> String {
+        let mut renamed = sql.to_string();
+        for (range, new) in rename_edits(sql, renames).edits.into_iter().rev() {
+            renamed.replace_range(range, &new);
+        }
+        renamed
+    }
+
+    #[test]
+    fn tables() {
+        let renames = [Rename::table("users=accounts").unwrap()];
+        assert_eq!(
+            rename(
+                "SELECT users.id, u.name FROM users u JOIN users_archive ua ON ua.id = users.id \
+                 WHERE u.note = 'users' -- users",
+                &renames
+            ),
+            "SELECT accounts.id, u.name FROM accounts u JOIN users_archive ua ON ua.id = accounts.id \
+             WHERE u.note = 'users' -- users"
+        );
+        assert_eq!(
+            rename("INSERT INTO public.USERS (id) VALUES (?)", &renames),
+            "INSERT INTO public.accounts (id) VALUES (?)"
+        );
+        assert_eq!(
+            rename(r#"DELETE FROM "users"; DELETE FROM "Users""#, &renames),
+            r#"DELETE FROM "accounts"; DELETE FROM "Users""#
+        );
+        assert_eq!(
+            rename("SELECT id FROM [users] JOIN `users` ON 1", &renames),
+            "SELECT id FROM [accounts] JOIN `accounts` ON 1"
+        );
+        assert_eq!(
+            rename(
+                "SELECT id FROM users",
+                &[Rename::table("users=order").unwrap()]
+            ),
+            r#"SELECT id FROM "order""#
+        );
+        assert_eq!(
+            rename(
+                "SELECT id FROM other.users",
+                &[Rename::table("public.users=accounts").unwrap()]
+            ),
+            "SELECT id FROM other.users"
+        );
+    }
+
+    #[test]
+    fn columns() {
+        let renames = [Rename::column("users.name=full_name").unwrap()];
+        assert_eq!(
+            rename(
+                "UPDATE users SET name = ? WHERE name = 'name' AND id = ?",
+                &renames
+            ),
+            "UPDATE users SET full_name = ? WHERE full_name = 'name' AND id = ?"
+        );
+        assert_eq!(
+            rename(
+                "SELECT u.name, o.name AS name FROM users u JOIN orders o ON o.user_id = u.id",
+                &renames
+            ),
+            "SELECT u.full_name, o.name AS name FROM users u JOIN orders o ON o.user_id = u.id"
+        );
+        assert_eq!(
+            rename(
+                "SELECT name FROM orders",
+                &[Rename::column("name=title").unwrap()]
+            ),
+            "SELECT title FROM orders"
+        );
+        assert!(Rename::column("name").is_err());
+    }
+
+    #[test]
+    fn ambiguous_columns_are_skipped() {
+        let renames = [Rename::column("users.name=full_name").unwrap()];
+        let sql = "SELECT name FROM users JOIN orders ON orders.user_id = users.id";
+        let renamed = rename_edits(sql, &renames);
+        assert!(renamed.edits.is_empty());
+        assert_eq!(
+            renamed.skipped,
+            vec![(
+                7..11,
+                "can't tell whether `name` is a column of `users`".to_string()
+            )]
+        );
+
+        let sql = "INSERT INTO users (name) SELECT s.name FROM staging s";
+        let renamed = rename_edits(sql, &renames);
+        assert_eq!(renamed.edits, vec![(19..23, "full_name".to_string())]);
+        assert!(renamed.skipped.is_empty());
+        assert!(Rename::table("users=public.accounts").is_err());
+    }
+}
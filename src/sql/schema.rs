@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::lexer::{Token, TokenKind};
+use super::split::statement_tokens;
+
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+const CONSTRAINT_KEYWORDS: &[&str] = &[
+    "CHECK",
+    "CONSTRAINT",
+    "EXCLUDE",
+    "FOREIGN",
+    "INDEX",
+    "KEY",
+    "PRIMARY",
+    "UNIQUE",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub name: String,
+    /// Known columns, `None` when they can't be determined (eg. views).
+    pub columns: Option<Vec<String>>,
+}
+
+impl Table {
+    pub fn has_column(&self, column: &str) -> bool {
+        match &self.columns {
+            Some(columns) => columns.iter().any(|c| c.eq_ignore_ascii_case(column)),
+            None => true,
+        }
+    }
+
+    pub fn suggest_column(&self, column: &str) -> Option<&str> {
+        let columns = self.columns.as_ref()?;
+        closest(column, columns.iter().map(String::as_str))
+    }
+}
+
+/// Tables and columns known to exist, loaded from DDL statements or a SQLite database.
+#[derive(Debug, Default)]
+pub struct Schema {
+    tables: HashMap<String, Table>,
+}
+
+impl Schema {
+    /// Load a SQLite database file or, failing that, a file of DDL statements.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        if bytes.starts_with(SQLITE_HEADER) {
+            return Self::from_sqlite(path);
+        }
+        Ok(Self::from_ddl(&String::from_utf8(bytes)?))
+    }
+
+    pub fn from_sqlite(path: &Path) -> anyhow::Result<Self> {
+        let conn = rusqlite::Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?;
+
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type IN ('table', 'view') ORDER BY name",
+        )?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut schema = Self::default();
+        for name in names {
+            let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+            let columns = stmt
+                .query_map([&name], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            schema.add_table(name, Some(columns));
+        }
+        Ok(schema)
+    }
+
+    /// Build a schema by replaying `CREATE`, `ALTER` and `DROP` statements in order.
+    pub fn from_ddl(ddl: &str) -> Self {
+        let mut schema = Self::default();
+        for stmt in &statement_tokens(ddl) {
+            match stmt.first() {
+                Some(tok) if tok.is_keyword("CREATE") => schema.create(stmt),
+                Some(tok) if tok.is_keyword("ALTER") => schema.alter(stmt),
+                Some(tok) if tok.is_keyword("DROP") => schema.drop(stmt),
+                _ => (),
+            }
+        }
+        schema
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Look up a table by its (possibly schema qualified) name, case insensitively.
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.key(name).and_then(|key| self.tables.get(&key))
+    }
+
+    fn key(&self, name: &str) -> Option<String> {
+        let name = name.to_lowercase();
+        if self.tables.contains_key(&name) {
+            return Some(name);
+        }
+        let base = name.rsplit('.').next().unwrap_or(&name);
+        self.tables
+            .keys()
+            .find(|key| key.rsplit('.').next() == Some(base))
+            .cloned()
+    }
+
+    pub fn suggest_table(&self, name: &str) -> Option<&str> {
+        closest(name, self.tables.values().map(|table| table.name.as_str()))
+    }
+
+    fn add_table(&mut self, name: String, columns: Option<Vec<String>>) {
+        self.tables
+            .insert(name.to_lowercase(), Table { name, columns });
+    }
+
+    fn table_mut(&mut self, name: &str) -> Option<&mut Table> {
+        let key = self.key(name)?;
+        self.tables.get_mut(&key)
+    }
+
+    fn create(&mut self, stmt: &[Token]) {
+        let Some(kind_idx) = stmt
+            .iter()
+            .position(|tok| tok.is_keyword("TABLE") || tok.is_keyword("VIEW"))
+        else {
+            return;
+        };
+        let idx = skip_keywords(stmt, kind_idx + 1, &["IF", "NOT", "EXISTS"]);
+        let Some((name, idx)) = dotted_name(stmt, idx) else {
+            return;
+        };
+
+        let columns = if stmt[kind_idx].is_keyword("TABLE")
+            && stmt.get(idx).is_some_and(|tok| tok.is_punct("("))
+        {
+            Some(column_definitions(&stmt[idx + 1..]))
+        } else {
+            None
+        };
+        self.add_table(name, columns);
+    }
+
+    fn alter(&mut self, stmt: &[Token]) {
+        if !stmt.get(1).is_some_and(|tok| tok.is_keyword("TABLE")) {
+            return;
+        }
+        let idx = skip_keywords(stmt, 2, &["IF", "EXISTS", "ONLY"]);
+        let Some((name, mut idx)) = dotted_name(stmt, idx) else {
+            return;
+        };
+
+        let action = match stmt.get(idx) {
+            Some(tok) if tok.kind == TokenKind::Word => tok.text.to_ascii_uppercase(),
+            _ => return,
+        };
+        idx = skip_keywords(stmt, idx + 1, &["COLUMN", "IF", "NOT", "EXISTS"]);
+
+        match action.as_str() {
+            "RENAME" if stmt.get(idx).is_some_and(|tok| tok.is_keyword("TO")) => {
+                let table = self.key(&name).and_then(|key| self.tables.remove(&key));
+                if let Some(mut table) = table {
+                    if let Some((new_name, _)) = dotted_name(stmt, idx + 1) {
+                        table.name = new_name;
+                        self.tables.insert(table.name.to_lowercase(), table);
+                    }
+                }
+            }
+            "RENAME" => {
+                let old = stmt.get(idx).map(|tok| tok.unquoted().to_string());
+                let new = stmt.get(idx + 2).map(|tok| tok.unquoted().to_string());
+                if let (Some(old), Some(new), Some(table)) = (old, new, self.table_mut(&name)) {
+                    if let Some(columns) = table.columns.as_mut() {
+                        for column in columns.iter_mut() {
+                            if column.eq_ignore_ascii_case(&old) {
+                                *column = new.clone();
+                            }
+                        }
+                    }
+                }
+            }
+            "ADD" => {
+                let column = stmt.get(idx).filter(|tok| !is_constraint(tok));
+                if let (Some(column), Some(table)) = (column, self.table_mut(&name)) {
+                    if let Some(columns) = table.columns.as_mut() {
+                        columns.push(column.unquoted().to_string());
+                    }
+                }
+            }
+            "DROP" => {
+                let column = stmt.get(idx).map(|tok| tok.unquoted());
+                if let (Some(column), Some(table)) = (column, self.table_mut(&name)) {
+                    if let Some(columns) = table.columns.as_mut() {
+                        columns.retain(|c| !c.eq_ignore_ascii_case(column));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn drop(&mut self, stmt: &[Token]) {
+        if !stmt
+            .get(1)
+            .is_some_and(|tok| tok.is_keyword("TABLE") || tok.is_keyword("VIEW"))
+        {
+            return;
+        }
+        let idx = skip_keywords(stmt, 2, &["IF", "EXISTS"]);
+        if let Some(key) = dotted_name(stmt, idx).and_then(|(name, _)| self.key(&name)) {
+            self.tables.remove(&key);
+        }
+    }
+}
+
+fn is_constraint(tok: &Token) -> bool {
+    CONSTRAINT_KEYWORDS.iter().any(|kw| tok.is_keyword(kw))
+}
+
+fn skip_keywords(stmt: &[Token], mut idx: usize, keywords: &[&str]) -> usize {
+    while stmt
+        .get(idx)
+        .is_some_and(|tok| keywords.iter().any(|kw| tok.is_keyword(kw)))
+    {
+        idx += 1;
+    }
+    idx
+}
+
+fn dotted_name(stmt: &[Token], idx: usize) -> Option<(String, usize)> {
+    let is_name = |tok: &Token| matches!(tok.kind, TokenKind::Word | TokenKind::QuotedIdent);
+
+    let first = stmt.get(idx).filter(|tok| is_name(tok))?;
+    let mut parts = vec![first.unquoted()];
+    let mut next = idx + 1;
+    while stmt.get(next).is_some_and(|tok| tok.is_punct(".")) {
+        match stmt.get(next + 1).filter(|tok| is_name(tok)) {
+            Some(part) => parts.push(part.unquoted()),
+            None => break,
+        }
+        next += 2;
+    }
+    Some((parts.join("."), next))
+}
+
+/// Column names from the body of a `CREATE TABLE`, starting after the opening parenthesis.
+fn column_definitions(body: &[Token]) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut depth = 0;
+    let mut element_start = true;
+    for tok in body {
+        if depth == 0 && element_start {
+            if matches!(tok.kind, TokenKind::Word | TokenKind::QuotedIdent) && !is_constraint(tok) {
+                columns.push(tok.unquoted().to_string());
+            }
+            element_start = false;
+        }
+
+        if tok.is_punct("(") {
+            depth += 1;
+        } else if tok.is_punct(")") {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        } else if tok.is_punct(",") && depth == 0 {
+            element_start = true;
+        }
+    }
+    columns
+}
+
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    candidates
+        .map(|candidate| {
+            let score = strsim::jaro_winkler(&name, &candidate.to_lowercase());
+            (score, candidate)
+        })
+        .filter(|(score, _)| *score >= 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DDL: &str = r#"
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    email VARCHAR(255),
+    CONSTRAINT uq_email UNIQUE (email)
+);
+CREATE TABLE [dbo].[orders] (id INT, user_id INT, total DECIMAL(10, 2));
+CREATE VIEW active_users AS SELECT * FROM users;
+CREATE TABLE scratch (x INT);
+ALTER TABLE users ADD COLUMN created_at TIMESTAMP;
+ALTER TABLE users DROP COLUMN email;
+ALTER TABLE orders RENAME COLUMN total TO amount;
+DROP TABLE scratch;
+"#;
+
+    #[test]
+    fn ddl_tables() {
+        let schema = Schema::from_ddl(DDL);
+        let users = schema.table("USERS").unwrap();
+        assert_eq!(
+            users.columns,
+            Some(vec!["id".into(), "name".into(), "created_at".into()])
+        );
+
+        let orders = schema.table("orders").unwrap();
+        assert_eq!(orders.name, "dbo.orders");
+        assert_eq!(
+            orders.columns,
+            Some(vec!["id".into(), "user_id".into(), "amount".into()])
+        );
+
+        assert!(schema.table("active_users").unwrap().has_column("anything"));
+        assert!(schema.table("scratch").is_none());
+    }
+
+    #[test]
+    fn suggestions() {
+        let schema = Schema::from_ddl(DDL);
+        assert_eq!(schema.suggest_table("user"), Some("users"));
+        assert_eq!(schema.suggest_table("invoices"), None);
+
+        let users = schema.table("users").unwrap();
+        assert_eq!(users.suggest_column("nmae"), Some("name"));
+        assert_eq!(users.suggest_column("zzz"), None);
+    }
+
+    #[test]
+    fn sqlite_database() {
+        let path = std::env::temp_dir().join(format!("sql-parse-schema-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch("CREATE TABLE users (id INTEGER, name TEXT);")
+                .unwrap();
+        }
+
+        let schema = Schema::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let users = schema.table("users").unwrap();
+        assert_eq!(users.columns, Some(vec!["id".into(), "name".into()]));
+    }
+}
@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+use super::lexer::{tokenize, Token, TokenKind};
+
+/// Byte ranges of each statement in `sql`, without surrounding whitespace, comments or the
+/// separator. Statements end at a `;` outside of any `BEGIN ... END` body or at a T-SQL `GO`
+/// line.
+pub fn split_statements(sql: &str) -> Vec<Range<usize>> {
+    let tokens = tokenize(sql);
+    statement_spans(&tokens)
+        .into_iter()
+        .filter_map(|span| {
+            let mut non_trivia = tokens[span].iter().filter(|tok| !tok.is_trivia());
+            let first = non_trivia.next()?;
+            let last = non_trivia.next_back().unwrap_or(first);
+            Some(first.range.start..last.range.end)
+        })
+        .collect()
+}
+
+/// Tokens of each statement in `sql` without trivia or the separator.
+pub fn statement_tokens(sql: &str) -> Vec<Vec<Token<'_>>> {
+    let tokens = tokenize(sql);
+    statement_spans(&tokens)
+        .into_iter()
+        .map(|span| {
+            tokens[span]
+                .iter()
+                .filter(|tok| !tok.is_trivia())
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+/// Token index ranges of each statement, excluding separators.
+fn statement_spans(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut depth = 0_usize;
+
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let tok = &tokens[idx];
+        if tok.kind != TokenKind::Word && !tok.is_punct(";") {
+            idx += 1;
+            continue;
+        }
+
+        let next = next_word(tokens, idx);
+        if tok.is_punct(";") && depth == 0 {
+            spans.push(start..idx);
+            start = idx + 1;
+        } else if tok.is_keyword("CASE") || (tok.is_keyword("BEGIN") && opens_block(next)) {
+            depth += 1;
+        } else if tok.is_keyword("END") {
+            let closes_other = ["IF", "LOOP", "WHILE", "REPEAT"]
+                .iter()
+                .any(|kw| next.is_some_and(|next| next.is_keyword(kw)));
+            if !closes_other {
+                depth = depth.saturating_sub(1);
+            }
+            // `END CASE` closes the CASE it names rather than opening another one
+            if next.is_some_and(|next| next.is_keyword("CASE")) {
+                idx += 1 + tokens[idx + 1..]
+                    .iter()
+                    .position(|tok| !tok.is_trivia())
+                    .unwrap_or(0);
+            }
+        } else if let Some(end) = go_line(tokens, idx) {
+            spans.push(start..idx);
+            start = end;
+            depth = 0;
+            idx = end;
+            continue;
+        }
+        idx += 1;
+    }
+    spans.push(start..tokens.len());
+    spans
+}
+
+fn next_word<'t, 'a>(tokens: &'t [Token<'a>], idx: usize) -> Option<&'t Token<'a>> {
+    tokens[idx + 1..].iter().find(|tok| !tok.is_trivia())
+}
+
+/// Whether `BEGIN` starts a compound statement rather than a transaction.
+fn opens_block(next: Option<&Token>) -> bool {
+    match next {
+        Some(next) => ![
+            ";",
+            "TRANSACTION",
+            "TRAN",
+            "WORK",
+            "DEFERRED",
+            "IMMEDIATE",
+            "EXCLUSIVE",
+            "DISTRIBUTED",
+        ]
+        .iter()
+        .any(|kw| next.is_keyword(kw) || next.is_punct(kw)),
+        None => false,
+    }
+}
+
+/// When the token at `idx` is a `GO` alone on its line, optionally followed by a count,
+/// the index of the token after that line.
+fn go_line(tokens: &[Token], idx: usize) -> Option<usize> {
+    if !tokens[idx].is_keyword("GO") {
+        return None;
+    }
+    let line_start = match idx.checked_sub(1).map(|prev| &tokens[prev]) {
+        None => true,
+        Some(prev) => prev.kind == TokenKind::Whitespace && prev.text.contains('\n'),
+    };
+    if !line_start {
+        return None;
+    }
+
+    let mut end = idx + 1;
+    let at = |end: usize| tokens.get(end);
+    if at(end).is_some_and(|tok| tok.kind == TokenKind::Whitespace && !tok.text.contains('\n')) {
+        end += 1;
+    }
+    if at(end).is_some_and(|tok| tok.kind == TokenKind::Number) {
+        end += 1;
+    }
+    match at(end) {
+        None => Some(end),
+        Some(tok) if tok.kind == TokenKind::Whitespace && tok.text.contains('\n') => Some(end),
+        Some(tok) if tok.kind == TokenKind::Comment => Some(end),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn split(sql: &str) -> Vec<&str> {
+        split_statements(sql)
+            .into_iter()
+            .map(|range| &sql[range])
+            .collect()
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(
+            split("SELECT ';'; -- a; comment\n SELECT \"a;b\" ;; /* ; */"),
+            vec!["SELECT ';'", "SELECT \"a;b\""]
+        );
+        assert_eq!(
+            split("CREATE TABLE t (id INT)\nGO\nINSERT INTO t VALUES (1)\ngo 2\nSELECT go FROM t"),
+            vec![
+                "CREATE TABLE t (id INT)",
+                "INSERT INTO t VALUES (1)",
+                "SELECT go FROM t"
+            ]
+        );
+        assert_eq!(split("SELECT 1;\n"), vec!["SELECT 1"]);
+    }
+
+    #[test]
+    fn blocks() {
+        let trigger = "CREATE TRIGGER t AFTER INSERT ON a BEGIN \
+                       UPDATE b SET n = CASE WHEN n > 0 THEN n ELSE 0 END; \
+                       DELETE FROM c; END";
+        assert_eq!(
+            split(&format!("{trigger}; SELECT 1")),
+            vec![trigger, "SELECT 1"]
+        );
+        assert_eq!(
+            split("BEGIN TRANSACTION; DELETE FROM a; COMMIT"),
+            vec!["BEGIN TRANSACTION", "DELETE FROM a", "COMMIT"]
+        );
+        assert_eq!(
+            split("BEGIN; DELETE FROM a; END;"),
+            vec!["BEGIN", "DELETE FROM a", "END"]
+        );
+        assert_eq!(
+            split("BEGIN IF x THEN SELECT 1; END IF; SELECT 2; END; SELECT 3"),
+            vec![
+                "BEGIN IF x THEN SELECT 1; END IF; SELECT 2; END",
+                "SELECT 3"
+            ]
+        );
+        assert_eq!(
+            split("BEGIN CASE x WHEN 1 THEN SELECT 1; ELSE SELECT 2; END CASE; END; SELECT 1"),
+            vec![
+                "BEGIN CASE x WHEN 1 THEN SELECT 1; ELSE SELECT 2; END CASE; END",
+                "SELECT 1"
+            ]
+        );
+    }
+}
@@ -1,17 +1,131 @@
+use std::collections::HashSet;
+
+use regex::Regex;
 use tree_sitter::{Node, Parser, Query, QueryCursor};
 
 use super::*;
 
+const USER_INPUT_PATTERN: &str =
+    r"\b(?:request|sys\.argv|os\.environ|os\.getenv|sys\.stdin)\b|\binput\s*\(";
+
 pub struct Exec {
     parser: Parser,
     query: Query,
+    user_input: Regex,
+}
+
+/// A single `crs.execute(...)`/`crs.executemany(...)` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecCall {
+    pub call: Position,
+    /// The first argument, holding the SQL expression.
+    pub sql_arg: Position,
+    pub blocks: Vec<SqlBlock>,
+    pub interpolations: Vec<Interpolation>,
+    /// The argument holding the bound parameters, if any.
+    pub params: Option<Params>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    pub position: Position,
+    pub shape: ParamsShape,
+}
+
+/// What can be told about bound parameters without running the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamsShape {
+    /// A literal tuple or list with this many values.
+    Sequence(usize),
+    /// A literal dict with these keys.
+    Mapping(Vec<String>),
+    /// Anything else, eg. a variable or a splat.
+    Dynamic,
+}
+
+/// How a Python value ends up spliced into the SQL text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationKind {
+    FString,
+    Format,
+    Concat,
+    Percent,
+}
+
+/// Where an interpolated value most likely comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    /// A module level `UPPER_CASE` name or attribute.
+    Constant,
+    /// Anything else, eg. local variables or computed values.
+    Unknown,
+    /// Derived from a parameter of the enclosing function.
+    Parameter,
+    /// Derived from request data, `input()`, `sys.argv` or the environment.
+    UserInput,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpolation {
+    pub kind: InterpolationKind,
+    pub origin: Origin,
+    /// Python source of the interpolated expression.
+    pub expr: String,
+    pub position: Position,
 }
 
 impl Exec {
     pub fn new() -> Self {
         let parser = new_parser();
         let query = new_query(include_str!("../../queries/execute.scm"));
-        Self { parser, query }
+        let user_input = Regex::new(USER_INPUT_PATTERN).unwrap();
+        Self {
+            parser,
+            query,
+            user_input,
+        }
+    }
+
+    pub fn calls(&mut self, code: SourceCode) -> Vec<ExecCall> {
+        let tree = parser_tree(&mut self.parser, code);
+        let constants = module_constants(tree.root_node(), code);
+
+        let mut cursor = QueryCursor::new();
+        let matches = cursor.matches(&self.query, tree.root_node(), code.as_bytes());
+
+        let mut calls = Vec::new();
+        for m in matches {
+            'outer: for cap in m.captures {
+                if cap.node.kind() != "argument_list" {
+                    continue;
+                }
+
+                let mut sql_blocks = Vec::new();
+                let mut tree_cursor = cap.node.walk();
+                for arg_node in cap.node.children(&mut tree_cursor) {
+                    match arg_node.kind() {
+                        "string" => self.basic_string_sql(arg_node, &mut sql_blocks),
+                        "call" => self.format_string_sql(arg_node, &mut sql_blocks),
+                        "concatenated_string" => self.weird_concat_str(arg_node, &mut sql_blocks),
+                        "binary_operator" => self.binary_operator_str(arg_node, &mut sql_blocks),
+                        _ => continue,
+                    };
+
+                    let mut interpolations = Vec::new();
+                    self.interpolations(arg_node, code, &constants, &mut interpolations);
+                    let call_node = cap.node.parent().unwrap_or(cap.node);
+                    calls.push(ExecCall {
+                        call: call_node.into(),
+                        sql_arg: arg_node.into(),
+                        blocks: sql_blocks,
+                        interpolations,
+                        params: params(call_node, arg_node, code),
+                    });
+                    break 'outer;
+                }
+            }
+        }
+        calls
     }
 
     fn basic_string_sql(&self, str_node: Node<'_>, sql_blocks: &mut Vec<SqlBlock>) {
@@ -62,36 +176,324 @@ impl Exec {
             }
         }
     }
+
+    fn interpolations(
+        &self,
+        node: Node<'_>,
+        code: SourceCode,
+        constants: &HashSet<String>,
+        found: &mut Vec<Interpolation>,
+    ) {
+        let push = |found: &mut Vec<Interpolation>, kind, expr: Node<'_>| {
+            if !is_literal(expr) {
+                found.push(Interpolation {
+                    kind,
+                    origin: self.origin(expr, code, constants),
+                    expr: code[expr.byte_range()].to_string(),
+                    position: expr.into(),
+                });
+            }
+        };
+
+        let mut tc = node.walk();
+        match node.kind() {
+            "string" => {
+                for child in node.children(&mut tc) {
+                    if let Some(expr) = child
+                        .child_by_field_name("expression")
+                        .filter(|_| child.kind() == "interpolation")
+                    {
+                        push(found, InterpolationKind::FString, expr);
+                    }
+                }
+            }
+            "concatenated_string" | "parenthesized_expression" => {
+                for child in node.named_children(&mut tc) {
+                    self.interpolations(child, code, constants, found);
+                }
+            }
+            "call" => {
+                let Some(attr_node) = node.child_by_field_name("function") else {
+                    return;
+                };
+                let is_format = attr_node
+                    .child_by_field_name("attribute")
+                    .is_some_and(|attr| &code[attr.byte_range()] == "format");
+                if !is_format {
+                    return;
+                }
+
+                if let Some(str_node) = attr_node.child_by_field_name("object") {
+                    self.interpolations(str_node, code, constants, found);
+                }
+                if let Some(args) = node.child_by_field_name("arguments") {
+                    for arg in args.named_children(&mut tc) {
+                        if arg.kind() != "comment" {
+                            let value = arg.child_by_field_name("value").unwrap_or(arg);
+                            push(found, InterpolationKind::Format, value);
+                        }
+                    }
+                }
+            }
+            "binary_operator" => {
+                let operator = node
+                    .child_by_field_name("operator")
+                    .map(|op| &code[op.byte_range()]);
+                let (Some(left), Some(right)) = (
+                    node.child_by_field_name("left"),
+                    node.child_by_field_name("right"),
+                ) else {
+                    return;
+                };
+
+                match operator {
+                    Some("+") => {
+                        for operand in [left, right] {
+                            if is_sql_fragment(operand) {
+                                self.interpolations(operand, code, constants, found);
+                            } else {
+                                push(found, InterpolationKind::Concat, operand);
+                            }
+                        }
+                    }
+                    Some("%") => {
+                        self.interpolations(left, code, constants, found);
+                        match right.kind() {
+                            "tuple" => {
+                                for value in right.named_children(&mut tc) {
+                                    push(found, InterpolationKind::Percent, value);
+                                }
+                            }
+                            "dictionary" => {
+                                for pair in right.named_children(&mut tc) {
+                                    let value = pair.child_by_field_name("value").unwrap_or(pair);
+                                    push(found, InterpolationKind::Percent, value);
+                                }
+                            }
+                            _ => push(found, InterpolationKind::Percent, right),
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn origin(&self, expr: Node<'_>, code: SourceCode, constants: &HashSet<String>) -> Origin {
+        let text = &code[expr.byte_range()];
+        if self.user_input.is_match(text) {
+            return Origin::UserInput;
+        }
+
+        let parameters = enclosing_parameters(expr, code);
+        let mut identifiers = Vec::new();
+        collect_identifiers(expr, &mut identifiers);
+        if identifiers
+            .iter()
+            .any(|ident| parameters.contains(&code[ident.byte_range()]))
+        {
+            return Origin::Parameter;
+        }
+
+        let is_constant = match expr.kind() {
+            "identifier" => is_constant_name(text) && constants.contains(text),
+            "attribute" => expr
+                .child_by_field_name("attribute")
+                .is_some_and(|attr| is_constant_name(&code[attr.byte_range()])),
+            _ => false,
+        };
+        if is_constant {
+            Origin::Constant
+        } else {
+            Origin::Unknown
+        }
+    }
 }
 
 impl TreesitterQuery for Exec {
     fn sql_blocks(&mut self, code: SourceCode) -> Vec<SqlBlock> {
-        let tree = parser_tree(&mut self.parser, code);
-
-        let mut cursor = QueryCursor::new();
-        let matches = cursor.matches(&self.query, tree.root_node(), code.as_bytes());
+        self.calls(code)
+            .into_iter()
+            .flat_map(|call| call.blocks)
+            .collect()
+    }
+}
 
-        let mut sql_blocks = Vec::new();
-        for m in matches {
-            'outer: for cap in m.captures {
-                if cap.node.kind() != "argument_list" {
-                    continue;
+/// The bound parameters following the SQL argument, positionally or as `params=`.
+fn params(call_node: Node<'_>, sql_arg: Node<'_>, code: SourceCode) -> Option<Params> {
+    let mut node = sql_arg.next_named_sibling();
+    while let Some(arg) = node {
+        let value = match arg.kind() {
+            "comment" => None,
+            "keyword_argument" => arg
+                .child_by_field_name("name")
+                .filter(|name| matches!(&code[name.byte_range()], "params" | "parameters"))
+                .and_then(|_| arg.child_by_field_name("value")),
+            _ => Some(arg),
+        };
+        if let Some(value) = value {
+            let many = call_node
+                .child_by_field_name("function")
+                .and_then(|func| func.child_by_field_name("attribute"))
+                .is_some_and(|attr| &code[attr.byte_range()] == "executemany");
+            // `executemany` takes a sequence of parameter sets, all shaped like the first
+            let shape = if many {
+                match value.kind() {
+                    "list" | "tuple" => value
+                        .named_child(0)
+                        .map(|first| params_shape(first, code))
+                        .unwrap_or(ParamsShape::Dynamic),
+                    _ => ParamsShape::Dynamic,
                 }
+            } else {
+                params_shape(value, code)
+            };
+            return Some(Params {
+                position: value.into(),
+                shape,
+            });
+        }
+        node = arg.next_named_sibling();
+    }
+    None
+}
 
-                let mut tree_cursor = cap.node.walk();
-                for arg_node in cap.node.children(&mut tree_cursor) {
-                    match arg_node.kind() {
-                        "string" => self.basic_string_sql(arg_node, &mut sql_blocks),
-                        "call" => self.format_string_sql(arg_node, &mut sql_blocks),
-                        "concatenated_string" => self.weird_concat_str(arg_node, &mut sql_blocks),
-                        "binary_operator" => self.binary_operator_str(arg_node, &mut sql_blocks),
-                        _ => continue,
-                    };
-                    break 'outer;
+fn params_shape(node: Node<'_>, code: SourceCode) -> ParamsShape {
+    let mut tc = node.walk();
+    let mut children = node
+        .named_children(&mut tc)
+        .filter(|child| child.kind() != "comment");
+    match node.kind() {
+        "tuple" | "list" => {
+            let values = children.collect::<Vec<_>>();
+            if values.iter().any(|value| value.kind() == "list_splat") {
+                ParamsShape::Dynamic
+            } else {
+                ParamsShape::Sequence(values.len())
+            }
+        }
+        "dictionary" => {
+            let keys = children.try_fold(Vec::new(), |mut keys, pair| {
+                let key = pair
+                    .child_by_field_name("key")
+                    .filter(|key| key.kind() == "string" && is_literal(*key))?;
+                let mut tc = key.walk();
+                let content = key
+                    .children(&mut tc)
+                    .filter(|child| child.kind() == "string_content")
+                    .map(|child| &code[child.byte_range()])
+                    .collect::<String>();
+                keys.push(content);
+                Some(keys)
+            });
+            keys.map(ParamsShape::Mapping)
+                .unwrap_or(ParamsShape::Dynamic)
+        }
+        _ => ParamsShape::Dynamic,
+    }
+}
+
+fn is_literal(node: Node<'_>) -> bool {
+    match node.kind() {
+        "integer" | "float" | "true" | "false" | "none" => true,
+        "string" => {
+            let mut tc = node.walk();
+            let has_interpolation = node
+                .children(&mut tc)
+                .any(|child| child.kind() == "interpolation");
+            !has_interpolation
+        }
+        _ => false,
+    }
+}
+
+fn is_sql_fragment(node: Node<'_>) -> bool {
+    matches!(
+        node.kind(),
+        "string" | "concatenated_string" | "binary_operator" | "parenthesized_expression"
+    )
+}
+
+fn is_constant_name(name: &str) -> bool {
+    name.chars().any(|ch| ch.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit() || ch == '_')
+}
+
+/// Names assigned at the top level of the module.
+fn module_constants(root: Node<'_>, code: SourceCode) -> HashSet<String> {
+    let mut constants = HashSet::new();
+    let mut tc = root.walk();
+    for stmt in root.named_children(&mut tc) {
+        if stmt.kind() != "expression_statement" {
+            continue;
+        }
+        let Some(assignment) = stmt.named_child(0).filter(|n| n.kind() == "assignment") else {
+            continue;
+        };
+        if let Some(left) = assignment
+            .child_by_field_name("left")
+            .filter(|n| n.kind() == "identifier")
+        {
+            constants.insert(code[left.byte_range()].to_string());
+        }
+    }
+    constants
+}
+
+/// Parameter names of the function enclosing `node`.
+fn enclosing_parameters<'a>(node: Node<'_>, code: &'a str) -> HashSet<&'a str> {
+    let mut names = HashSet::new();
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if parent.kind() == "function_definition" {
+            let Some(params) = parent.child_by_field_name("parameters") else {
+                break;
+            };
+            let mut tc = params.walk();
+            for param in params.named_children(&mut tc) {
+                let ident = match param.kind() {
+                    "identifier" => Some(param),
+                    "default_parameter" | "typed_default_parameter" => {
+                        param.child_by_field_name("name")
+                    }
+                    _ => param.named_child(0).filter(|n| n.kind() == "identifier"),
+                };
+                if let Some(ident) = ident {
+                    names.insert(&code[ident.byte_range()]);
                 }
             }
+            break;
+        }
+        current = parent.parent();
+    }
+    names
+}
+
+fn collect_identifiers<'t>(node: Node<'t>, found: &mut Vec<Node<'t>>) {
+    if node.kind() == "identifier" {
+        found.push(node);
+        return;
+    }
+    // only the object of `obj.attr` names a variable
+    if node.kind() == "attribute" {
+        if let Some(object) = node.child_by_field_name("object") {
+            collect_identifiers(object, found);
+        }
+        return;
+    }
+    // keyword argument names aren't variables either
+    if node.kind() == "keyword_argument" {
+        if let Some(value) = node.child_by_field_name("value") {
+            collect_identifiers(value, found);
         }
-        sql_blocks.clone()
+        return;
+    }
+    let mut tc = node.walk();
+    for child in node.named_children(&mut tc) {
+        collect_identifiers(child, found);
     }
 }
 
@@ -149,6 +551,88 @@ crs.execute('green' + 'eggs' + 'spam')
             assert_eq!(sql, snippet);
         }
     }
+
+    #[test]
+    fn interpolations() {
+        let code = r#"
+TABLE = "users"
+uid = 1
+
+def lookup(name, *, limit=10):
+    crs.execute(f"SELECT * FROM {TABLE} WHERE name = {name!r} AND id = {uid} LIMIT {5}")
+    crs.execute("SELECT {} FROM t WHERE id = {id}".format(cols, id=request.args["id"]))
+    crs.execute("SELECT * FROM t WHERE a = '" + name.strip() + "'" + " LIMIT %d" % limit)
+    crs.execute("SELECT * FROM t WHERE a = %s", (name,))
+"#;
+
+        let calls = Exec::new().calls(code);
+        let found = calls
+            .iter()
+            .map(|call| {
+                call.interpolations
+                    .iter()
+                    .map(|i| (i.kind, i.origin, i.expr.as_str()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        use InterpolationKind::*;
+        use Origin::*;
+        assert_eq!(
+            found,
+            vec![
+                vec![
+                    (FString, Constant, "TABLE"),
+                    (FString, Parameter, "name"),
+                    (FString, Unknown, "uid"),
+                ],
+                vec![
+                    (Format, Unknown, "cols"),
+                    (Format, UserInput, r#"request.args["id"]"#),
+                ],
+                vec![
+                    (Concat, Parameter, "name.strip()"),
+                    (Percent, Parameter, "limit"),
+                ],
+                vec![],
+            ]
+        );
+        assert_eq!(calls[3].blocks.len(), 1);
+    }
+
+    #[test]
+    fn params() {
+        let code = r#"
+crs.execute("SELECT 1")
+crs.execute("SELECT ?, ?", (a, b))
+crs.execute("SELECT ?", [a])
+crs.execute("SELECT :a", {"a": 1, 'b': 2})
+crs.execute("SELECT :a", {**extra})
+crs.execute("SELECT ?", args)
+crs.execute("SELECT ?", params=(*args,))
+crs.executemany("INSERT INTO t VALUES (?, ?)", [(1, 2), (3, 4)])
+"#;
+        let shapes = Exec::new()
+            .calls(code)
+            .into_iter()
+            .map(|call| call.params.map(|params| params.shape))
+            .collect::<Vec<_>>();
+
+        use ParamsShape::*;
+        assert_eq!(
+            shapes,
+            vec![
+                None,
+                Some(Sequence(2)),
+                Some(Sequence(1)),
+                Some(Mapping(vec!["a".into(), "b".into()])),
+                Some(Dynamic),
+                Some(Dynamic),
+                Some(Dynamic),
+                Some(Sequence(2)),
+            ]
+        );
+    }
 }
 
 /*
@@ -0,0 +1,296 @@
+use std::ops::Range;
+
+use super::{new_parser, parser_tree, SqlBlock};
+
+/// The letters before a Python string's opening quotes, e.g. `rb` or `F`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StringPrefix<'a> {
+    /// The prefix as written.
+    pub text: &'a str,
+    pub raw: bool,
+    pub bytes: bool,
+    pub format: bool,
+}
+
+impl<'a> StringPrefix<'a> {
+    /// Split the text of a `string_start` node into its prefix and opening quotes. `None` when
+    /// the prefix isn't one Python accepts.
+    pub fn split(start: &'a str) -> Option<(Self, &'a str)> {
+        let delimiter = start.trim_start_matches(|ch: char| ch.is_ascii_alphabetic());
+        let text = &start[..start.len() - delimiter.len()];
+        let mut prefix = Self {
+            text,
+            ..Default::default()
+        };
+
+        let mut unicode = false;
+        for ch in text.chars() {
+            let flag = match ch.to_ascii_lowercase() {
+                'r' => &mut prefix.raw,
+                'b' => &mut prefix.bytes,
+                'f' => &mut prefix.format,
+                'u' => &mut unicode,
+                _ => return None,
+            };
+            if *flag {
+                return None;
+            }
+            *flag = true;
+        }
+
+        // `u` can't be combined, and neither can `b` and `f`
+        let invalid = (prefix.bytes && prefix.format) || (unicode && text.len() > 1);
+        (!invalid).then_some((prefix, delimiter))
+    }
+}
+
+/// The body of a string, written between `delimiter` quotes instead. Quotes are escaped only
+/// where they would end the string early, escaped quotes that no longer need it lose their
+/// backslash and every other escape is kept, so the value stays the same. `None` when that isn't
+/// possible: raw strings can't gain escapes and f-string replacement fields can't hold the new
+/// quote.
+pub fn reencode(body: &str, prefix: StringPrefix, delimiter: &str) -> Option<String> {
+    let quote = delimiter.chars().next()?;
+    let triple = delimiter.len() == 3;
+    let mut encoded = String::with_capacity(body.len());
+    // unescaped quotes in a row, the third one ends a triple-quoted string
+    let mut run = 0;
+    let mut chars = body.chars().peekable();
+    while let Some(mut ch) = chars.next() {
+        if ch == '\\' {
+            let next = chars.next()?;
+            if prefix.raw || !matches!(next, '\'' | '"') {
+                encoded.push(ch);
+                encoded.push(next);
+                run = 0;
+                continue;
+            }
+            ch = next;
+        } else if prefix.format && ch == '{' {
+            if chars.next_if_eq(&'{').is_some() {
+                encoded.push_str("{{");
+            } else {
+                encoded.push(ch);
+                let mut depth = 1;
+                while depth > 0 {
+                    let ch = chars.next()?;
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ if ch == quote => return None,
+                        _ => {}
+                    }
+                    encoded.push(ch);
+                }
+            }
+            run = 0;
+            continue;
+        }
+
+        let ends_string = !triple || run == 2 || chars.peek().is_none();
+        if ch == quote && ends_string {
+            if prefix.raw {
+                return None;
+            }
+            encoded.push('\\');
+            run = 0;
+        } else if ch == quote {
+            run += 1;
+        } else {
+            run = 0;
+        }
+        encoded.push(ch);
+    }
+    Some(encoded)
+}
+
+/// The body of a string written as the body of a plain string, or of an f-string when `format`,
+/// with the same value. Backslashes of raw strings are escaped and braces are doubled when the
+/// string becomes an f-string. Escaped quotes are kept, see [`reencode`].
+pub fn plain_body(body: &str, prefix: StringPrefix, format: bool) -> String {
+    let mut plain = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if prefix.raw => plain.push_str("\\\\"),
+            '\\' => {
+                plain.push(ch);
+                plain.extend(chars.next());
+            }
+            '{' if prefix.format && chars.peek() != Some(&'{') => {
+                // replacement fields are Python code, copied as is
+                plain.push(ch);
+                let mut depth = 1;
+                while let Some(ch) = chars.next_if(|_| depth > 0) {
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    plain.push(ch);
+                }
+            }
+            '{' | '}' if prefix.format => {
+                plain.push(ch);
+                plain.extend(chars.next_if_eq(&ch));
+            }
+            '{' | '}' if format => {
+                plain.push(ch);
+                plain.push(ch);
+            }
+            _ => plain.push(ch),
+        }
+    }
+    plain
+}
+
+/// The value of a string with the given body. Escapes are evaluated as Python does, except named
+/// `\N{...}` escapes, which are kept as written. Replacement fields of f-strings are also kept as
+/// written.
+pub fn string_value(body: &str, prefix: StringPrefix) -> String {
+    if prefix.raw {
+        return body.to_string();
+    }
+
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            value.push(ch);
+            continue;
+        }
+        let Some(next) = chars.next() else {
+            value.push(ch);
+            break;
+        };
+        let mut code_point = |radix: u32, len: usize, first: Option<char>| {
+            let mut digits = first.map(String::from).unwrap_or_default();
+            while digits.len() < len {
+                match chars.next_if(|ch| ch.is_digit(radix)) {
+                    Some(digit) => digits.push(digit),
+                    None if radix == 8 => break,
+                    None => return None,
+                }
+            }
+            u32::from_str_radix(&digits, radix)
+                .ok()
+                .and_then(char::from_u32)
+        };
+        let unescaped = match next {
+            '\n' => continue,
+            '\\' | '\'' | '"' => Some(next),
+            'a' => Some('\x07'),
+            'b' => Some('\x08'),
+            'f' => Some('\x0c'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'v' => Some('\x0b'),
+            '0'..='7' => code_point(8, 3, Some(next)),
+            'x' => code_point(16, 2, None),
+            'u' if !prefix.bytes => code_point(16, 4, None),
+            'U' if !prefix.bytes => code_point(16, 8, None),
+            _ => None,
+        };
+        match unescaped {
+            Some(ch) => value.push(ch),
+            None => {
+                value.push('\\');
+                value.push(next);
+            }
+        }
+    }
+    value
+}
+
+/// Value of the string literal spanning exactly `range` once `code` is parsed, if there is one.
+pub fn string_value_at(code: &str, range: Range<usize>) -> Option<String> {
+    let tree = parser_tree(&mut new_parser(), code);
+    let node = tree
+        .root_node()
+        .descendant_for_byte_range(range.start, range.end)?;
+    if node.kind() != "string" || node.byte_range() != range || node.has_error() {
+        return None;
+    }
+
+    let block = SqlBlock {
+        string_start: node.child(0)?.into(),
+        string_end: node.child(node.child_count() - 1)?.into(),
+    };
+    let (prefix, _) = block.opening(code)?;
+    Some(string_value(block.inner_text(code), prefix))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prefixes() {
+        let (prefix, delimiter) = StringPrefix::split("Rb'''").unwrap();
+        assert_eq!(prefix.text, "Rb");
+        assert_eq!(delimiter, "'''");
+        assert!(prefix.raw && prefix.bytes && !prefix.format);
+
+        let (prefix, _) = StringPrefix::split("fR\"").unwrap();
+        assert!(prefix.raw && prefix.format);
+        assert_eq!(StringPrefix::split("U'").unwrap().0.text, "U");
+        assert_eq!(
+            StringPrefix::split("\"").unwrap().0,
+            StringPrefix::default()
+        );
+
+        for invalid in ["ur'", "bf'", "rr'", "x'"] {
+            assert_eq!(StringPrefix::split(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn reencoded() {
+        let plain = StringPrefix::default();
+        let raw = StringPrefix::split("r'").unwrap().0;
+        let format = StringPrefix::split("f'").unwrap().0;
+
+        assert_eq!(reencode(r"It\'s", plain, "\"\"\"").unwrap(), "It's");
+        assert_eq!(
+            reencode(r#"say \"hi\""#, plain, "\"\"\"").unwrap(),
+            r#"say "hi\""#
+        );
+        assert_eq!(reencode(r#""""#, plain, "\"\"\"").unwrap(), r#""\""#);
+        assert_eq!(reencode(r#"a "b" \n"#, plain, "'").unwrap(), r#"a "b" \n"#);
+        assert_eq!(reencode("It's ", plain, "'").unwrap(), r"It\'s ");
+        assert_eq!(reencode(r"It\'s", raw, "\"").unwrap(), r"It\'s");
+        assert_eq!(reencode("It's", raw, "'"), None);
+        assert_eq!(
+            reencode(r"{{x}} {d['k']:{w}} It\'s", format, "\"").unwrap(),
+            r"{{x}} {d['k']:{w}} It's"
+        );
+        assert_eq!(reencode(r"{d['k']}", format, "'''"), None);
+    }
+
+    #[test]
+    fn values() {
+        let plain = StringPrefix::default();
+        assert_eq!(string_value(r"It\'s", plain), "It's");
+        assert_eq!(string_value("a\\\nb", plain), "ab");
+        assert_eq!(string_value(r"\x41\101é\t\d", plain), "AAé\t\\d");
+        assert_ne!(string_value(r"\\n", plain), string_value(r"\n", plain));
+        let raw = StringPrefix::split("R'").unwrap().0;
+        assert_eq!(string_value(r"\'", raw), r"\'");
+    }
+
+    #[test]
+    fn plain_bodies() {
+        let raw = StringPrefix::split("r'").unwrap().0;
+        let raw_format = StringPrefix::split("rf'").unwrap().0;
+        let plain = StringPrefix::default();
+
+        assert_eq!(plain_body(r"\d+ {x}", raw, false), r"\\d+ {x}");
+        assert_eq!(plain_body(r"\d+ {x}", raw, true), r"\\d+ {{x}}");
+        assert_eq!(
+            plain_body(r"\d {{ {x:{w}} }}", raw_format, true),
+            r"\\d {{ {x:{w}} }}"
+        );
+        assert_eq!(plain_body(r"It\'s {}", plain, true), r"It\'s {{}}");
+    }
+}
@@ -1,11 +1,13 @@
 mod custom;
 mod exec;
+mod literal;
 mod no_ts;
 
 use std::ops::Range;
 
 pub use custom::CustomQuery;
-pub use exec::Exec;
+pub use exec::{Exec, ExecCall, InterpolationKind, Origin, ParamsShape};
+pub use literal::{plain_body, reencode, string_value, string_value_at, StringPrefix};
 pub use no_ts::NoTS;
 use tree_sitter::{Node, Parser, Point, Query, Tree};
 use tree_sitter_python::language as Python;
@@ -46,6 +48,19 @@ pub fn parser_tree(parser: &mut Parser, code: SourceCode) -> Tree {
         .unwrap_or_else(|| error_exit!("Tree-sitter failed to parse code:\n{}", code))
 }
 
+/// Name of the innermost function containing the byte offset `byte`, if any.
+pub fn enclosing_function<'a>(tree: &Tree, code: &'a str, byte: usize) -> Option<&'a str> {
+    let mut current = tree.root_node().descendant_for_byte_range(byte, byte);
+    while let Some(node) = current {
+        if node.kind() == "function_definition" {
+            let name = node.child_by_field_name("name")?;
+            return Some(&code[name.byte_range()]);
+        }
+        current = node.parent();
+    }
+    None
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CaptureGroup {
     Sql,
@@ -98,4 +113,9 @@ impl SqlBlock {
     pub fn start_line_num(&self) -> usize {
         self.string_start.point.row + 1
     }
+
+    /// The string's prefix and opening quotes, `None` when the prefix isn't valid Python.
+    pub fn opening<'a>(&self, code: &'a str) -> Option<(StringPrefix<'a>, &'a str)> {
+        StringPrefix::split(&code[self.string_start.byte_range.clone()])
+    }
 }
@@ -1,6 +1,13 @@
+use std::borrow::Cow;
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use console::style;
+use encoding_rs::{Encoding, UTF_8};
+
+use crate::cli::ColumnUnit;
+use crate::journal;
 
 #[macro_export]
 macro_rules! error_exit {
@@ -54,25 +61,292 @@ pub(crate) fn iter_valid_files(paths: &[PathBuf]) -> impl Iterator<Item = (Strin
             return None;
         }
 
-        match std::fs::read_to_string(path) {
-            Ok(code) => Some((code, path)),
-            Err(_) => {
-                eprintln!("Failed to read file: {} -- skipping", path.display());
+        let Ok(bytes) = std::fs::read(path) else {
+            eprintln!("Failed to read file: {} -- skipping", path.display());
+            return None;
+        };
+        match TextFormat::decode(&bytes) {
+            Ok((_, code)) => Some((code, path)),
+            Err(err) => {
+                eprintln!(
+                    "Failed to decode file: {} ({err}) -- skipping",
+                    path.display()
+                );
                 None
             }
         }
     })
 }
 
+/// `path` made absolute with symlinks and `..` resolved, through its parent directory for a file
+/// that doesn't exist yet, so a file is known by the same path however it was named.
+pub(crate) fn resolve_path(path: &Path) -> PathBuf {
+    if let Ok(path) = std::fs::canonicalize(path) {
+        return path;
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (std::fs::canonicalize(parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+/// Write `bytes` to `path` through a temporary file renamed over it, so it's never left half
+/// written. The permissions of the file replaced are kept, and symlinks are written through.
 pub(crate) fn write_file<P>(path: &P, bytes: &[u8]) -> anyhow::Result<()>
 where
     P: AsRef<Path>,
 {
     use std::io::Write;
 
-    let mut file = std::fs::File::create(path)?;
-    file.write_all(bytes)?;
-    Ok(())
+    let path = resolve_path(path.as_ref());
+    let Some(file_name) = path.file_name() else {
+        anyhow::bail!("not a file: {}", path.display());
+    };
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".sql-parse-tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    let permissions = std::fs::metadata(&path).map(|meta| meta.permissions());
+    let written = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        if let Ok(permissions) = permissions {
+            std::fs::set_permissions(&tmp, permissions)?;
+        }
+        std::fs::rename(&tmp, &path)
+    })();
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    Ok(written?)
+}
+
+/// Replace the Python file at `path` with `code`, restoring the encoding, line endings, BOM and
+/// final newline the file was read with, see [`TextFormat`].
+pub(crate) fn write_code<P>(path: &P, code: &str, backup: Option<&str>) -> anyhow::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let original = std::fs::read(path)?;
+    let (format, _) = TextFormat::decode(&original)?;
+    rewrite_file(
+        path.as_ref(),
+        Some(&original),
+        &format.encode(code)?,
+        backup,
+    )
+}
+
+/// Write a file a rewrite changes, holding `original` or created when `None`. The original is
+/// backed up with the `backup` suffix appended to its name and recorded in the journal `undo`
+/// restores from, with a warning when that fails. Files the rewrite leaves as they were aren't
+/// touched.
+pub(crate) fn rewrite_file(
+    path: &Path,
+    original: Option<&[u8]>,
+    bytes: &[u8],
+    backup: Option<&str>,
+) -> anyhow::Result<()> {
+    if original == Some(bytes) {
+        return Ok(());
+    }
+    if let (Some(original), Some(suffix)) = (original, backup) {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(suffix);
+        write_file(&backup, original)?;
+    }
+    // a file `undo` can't restore is better than no rewrite at all
+    if let Err(err) = journal::record(path, original, bytes) {
+        eprintln!(
+            "WARNING: {} not recorded in the undo journal {}: {err}",
+            path.display(),
+            journal::JOURNAL_DIR
+        );
+    }
+    write_file(&path, bytes)
+}
+
+/// PEP 263 coding declaration, looked for in the first two lines.
+const CODING_PATTERN: &str = r"^[ \t\f]*#.*?coding[:=][ \t]*([-\w.]+)";
+
+static CODING_REGEX: OnceLock<regex::bytes::Regex> = OnceLock::new();
+
+/// A source file encoding. ISO-8859-1 and ASCII are Python's own, their WHATWG labels stand for
+/// windows-1252.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Codec {
+    Whatwg(&'static Encoding),
+    Latin1,
+    Ascii,
+}
+
+impl Codec {
+    /// The codec Python uses for the coding declaration `name`.
+    fn for_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase().replace('_', "-");
+        match name.as_str() {
+            "latin-1" | "latin1" | "latin" | "l1" | "iso-latin-1" | "iso-8859-1" | "iso8859-1"
+            | "8859" | "cp819" => return Some(Self::Latin1),
+            "ascii" | "us-ascii" | "646" => return Some(Self::Ascii),
+            _ => {}
+        }
+        // Python accepts `utf_8` and `utf8`, the WHATWG labels are `utf-8` and `utf8`
+        [name.clone(), name.replace('-', "")]
+            .iter()
+            .find_map(|label| Encoding::for_label(label.as_bytes()))
+            // UTF-16 labels decode but encode as UTF-8, Python doesn't allow them in source anyway
+            .filter(|encoding| encoding.output_encoding() == *encoding)
+            .map(Self::Whatwg)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Whatwg(encoding) => encoding.name(),
+            Self::Latin1 => "ISO-8859-1",
+            Self::Ascii => "ASCII",
+        }
+    }
+
+    /// `bytes` as text, `None` when they aren't valid in this encoding.
+    fn decode<'b>(&self, bytes: &'b [u8]) -> Option<Cow<'b, str>> {
+        match self {
+            Self::Whatwg(encoding) => {
+                encoding.decode_without_bom_handling_and_without_replacement(bytes)
+            }
+            Self::Latin1 => Some(bytes.iter().map(|&b| char::from(b)).collect()),
+            Self::Ascii => bytes
+                .is_ascii()
+                .then(|| Cow::Borrowed(std::str::from_utf8(bytes).unwrap())),
+        }
+    }
+
+    /// `text` in this encoding, `None` when it has characters the encoding can't hold.
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Whatwg(encoding) => {
+                let (bytes, _, unmappable) = encoding.encode(text);
+                (!unmappable).then(|| bytes.into_owned())
+            }
+            Self::Latin1 => text.chars().map(|ch| u8::try_from(ch).ok()).collect(),
+            Self::Ascii => text.is_ascii().then(|| text.as_bytes().to_vec()),
+        }
+    }
+}
+
+/// Encoding, line endings, BOM and final newline of a source file. Files are searched and edited
+/// as UTF-8 with `\n` line endings and no BOM, so byte offsets and columns line up, and written
+/// back as they were.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TextFormat {
+    /// From the BOM or the coding declaration, UTF-8 without either.
+    pub encoding: Codec,
+    /// Every line ends with `\r\n`. Files mixing line endings are left as they are.
+    pub crlf: bool,
+    pub bom: bool,
+    pub trailing_newline: bool,
+}
+
+impl TextFormat {
+    /// The format of a source file and its normalized text. Fails for unknown encodings and files
+    /// that aren't valid in theirs.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<(Self, String)> {
+        let (encoding, bom) = match bytes.strip_prefix(b"\xef\xbb\xbf") {
+            Some(_) => (Codec::Whatwg(UTF_8), true),
+            None => (
+                coding_declaration(bytes)?.unwrap_or(Codec::Whatwg(UTF_8)),
+                false,
+            ),
+        };
+        let body = if bom { &bytes[3..] } else { bytes };
+        let text = encoding
+            .decode(body)
+            .ok_or_else(|| anyhow::anyhow!("not valid {}", encoding.name()))?;
+
+        let newlines = text.matches('\n').count();
+        let format = Self {
+            encoding,
+            crlf: newlines > 0 && text.matches("\r\n").count() == newlines,
+            bom,
+            trailing_newline: text.ends_with('\n'),
+        };
+        let text = if format.crlf {
+            text.replace("\r\n", "\n")
+        } else {
+            text.into_owned()
+        };
+        Ok((format, text))
+    }
+
+    /// `code` in this format again. Every `\n`, including those of replacement text, becomes the
+    /// file's line ending. Fails when the encoding can't hold a character of `code`.
+    pub fn encode(&self, code: &str) -> anyhow::Result<Vec<u8>> {
+        let mut code = if self.trailing_newline {
+            code.to_string()
+        } else {
+            code.trim_end_matches(['\r', '\n']).to_string()
+        };
+        if self.trailing_newline && !code.ends_with('\n') {
+            code.push('\n');
+        }
+        if self.crlf {
+            code = code.replace("\r\n", "\n").replace('\n', "\r\n");
+        }
+
+        let Some(bytes) = self.encoding.encode(&code) else {
+            anyhow::bail!("can't be written as {}", self.encoding.name());
+        };
+        let bom: &[u8] = if self.bom { b"\xef\xbb\xbf" } else { b"" };
+        Ok([bom, &bytes].concat())
+    }
+}
+
+/// The encoding named by a `# -*- coding: name -*-` comment on one of the first two lines.
+fn coding_declaration(bytes: &[u8]) -> anyhow::Result<Option<Codec>> {
+    let re = CODING_REGEX.get_or_init(|| regex::bytes::Regex::new(CODING_PATTERN).unwrap());
+    let mut lines = bytes.split(|&b| b == b'\n');
+    let first = lines.next().unwrap_or_default();
+    // the second line only counts when the first is a comment or blank
+    let first_is_comment = first
+        .iter()
+        .find(|b| !b" \t\x0c\r".contains(b))
+        .is_none_or(|&b| b == b'#');
+    let candidates = [Some(first), lines.next().filter(|_| first_is_comment)];
+
+    let Some(caps) = candidates
+        .into_iter()
+        .flatten()
+        .find_map(|line| re.captures(line))
+    else {
+        return Ok(None);
+    };
+    let name = String::from_utf8_lossy(&caps[1]).to_ascii_lowercase();
+    let codec =
+        Codec::for_name(&name).ok_or_else(|| anyhow::anyhow!("unknown encoding `{name}`"))?;
+    Ok(Some(codec))
+}
+
+/// 1-based line number of the byte offset `byte` in `code`.
+pub(crate) fn line_number(code: &str, byte: usize) -> usize {
+    code.as_bytes()[..byte]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+        + 1
+}
+
+/// 1-based line and column, counted in `unit`, of the byte offset `byte` in `code`.
+pub(crate) fn line_col(code: &str, byte: usize, unit: ColumnUnit) -> (usize, usize) {
+    let line_start = code[..byte].rfind('\n').map_or(0, |idx| idx + 1);
+    (
+        line_number(code, byte),
+        unit.column(&code[line_start..byte]),
+    )
 }
 
 pub(crate) fn print(path: &str, lnum: usize, col: Option<usize>, text: &str) {
@@ -90,3 +364,109 @@ pub(crate) fn print(path: &str, lnum: usize, col: Option<usize>, text: &str) {
         text
     );
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    #[test]
+    fn text_format() {
+        let text = "\u{feff}crs.execute('''\r\n  SELECT 1\r\n''')\r\n";
+        let (format, code) = TextFormat::decode(text.as_bytes()).unwrap();
+        assert_eq!(
+            format,
+            TextFormat {
+                encoding: Codec::Whatwg(UTF_8),
+                crlf: true,
+                bom: true,
+                trailing_newline: true,
+            }
+        );
+        assert_eq!(code, "crs.execute('''\n  SELECT 1\n''')\n");
+        assert_eq!(
+            line_col(&code, code.find("SELECT").unwrap(), ColumnUnit::Bytes),
+            (2, 3)
+        );
+        assert_eq!(format.encode(&code).unwrap(), text.as_bytes());
+        assert_eq!(
+            format
+                .encode("crs.execute('''\nSELECT\r\n  1\n''')")
+                .unwrap(),
+            "\u{feff}crs.execute('''\r\nSELECT\r\n  1\r\n''')\r\n".as_bytes()
+        );
+
+        let mixed = "a\r\nb\nc";
+        let (format, code) = TextFormat::decode(mixed.as_bytes()).unwrap();
+        assert!(!format.crlf && !format.trailing_newline);
+        assert_eq!(format.encode(&code).unwrap(), mixed.as_bytes());
+        assert_eq!(format.encode("a\nb\n\n").unwrap(), b"a\nb");
+    }
+
+    #[test]
+    fn encodings() {
+        let latin = b"#!/usr/bin/env python\n# -*- coding: latin-1 -*-\nq = 'caf\xe9'\n";
+        let (format, code) = TextFormat::decode(latin).unwrap();
+        assert_eq!(format.encoding, Codec::Latin1);
+        assert!(code.ends_with("q = 'caf\u{e9}'\n"));
+        assert_eq!(format.encode(&code).unwrap(), latin);
+        assert!(format.encode("q = '\u{2603}'").is_err());
+        // unlike windows-1252, C1 controls are kept and `€` can't be written
+        let (_, code) = TextFormat::decode(b"# coding: iso_8859_1\nq = '\x80'\n").unwrap();
+        assert!(code.ends_with("q = '\u{80}'\n"));
+        assert!(format.encode("q = '\u{20ac}'").is_err());
+
+        let (format, _) = TextFormat::decode(b"# coding: cp1252\n").unwrap();
+        assert_eq!(format.encoding, Codec::Whatwg(WINDOWS_1252));
+        let (format, _) = TextFormat::decode(b"# coding: shift_jis\n").unwrap();
+        assert_eq!(format.encoding, Codec::Whatwg(SHIFT_JIS));
+
+        let (format, _) = TextFormat::decode(b"# coding: ascii\nq = 'x'\n").unwrap();
+        assert_eq!(format.encoding, Codec::Ascii);
+        assert!(TextFormat::decode(b"# coding: ascii\nq = '\xe9'\n").is_err());
+        assert!(format.encode("q = '\u{e9}'").is_err());
+
+        let code_first = b"import os\n# coding=latin-1\nq = 'caf\xe9'\n";
+        assert!(TextFormat::decode(code_first).is_err());
+        assert!(TextFormat::decode(b"# vim: set fileencoding=utf_8 :\n").is_ok());
+        let err = TextFormat::decode(b"# coding: klingon\n").unwrap_err();
+        assert_eq!(err.to_string(), "unknown encoding `klingon`");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_file_replaces_atomically() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = std::env::temp_dir().join(format!("sql-parse-write-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("query.py");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_file(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+
+        // the file behind a link is replaced, not the link
+        let link = dir.join("link.py");
+        symlink(&path, &link).unwrap();
+        write_file(&link, b"newer").unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "newer");
+
+        // the temporary file is removed when the rename fails
+        let sub = dir.join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        assert!(write_file(&sub, b"new").is_err());
+
+        let mut names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, ["link.py", "query.py", "sub"]);
+    }
+}